@@ -1,8 +1,14 @@
+use std::collections::BTreeMap;
+use std::env;
+use std::fmt;
+use std::fmt::Display;
+use std::fmt::Formatter;
 use std::fs::File;
 use std::io::Read;
 use std::path::Path;
 
 #[cfg(test)]
+#[allow(clippy::items_after_test_module)]
 mod tests {
     use super::*;
 
@@ -10,9 +16,7 @@ mod tests {
     fn test_compute_priority() {
         let item_types = ['a', 'A', 'z', 'Z'];
         let expected_priorities = [1, 27, 26, 52];
-        for (item_type, expected_priority) in
-            item_types.into_iter().zip(expected_priorities.into_iter())
-        {
+        for (item_type, expected_priority) in item_types.into_iter().zip(expected_priorities) {
             assert_eq!(compute_priority(&item_type), expected_priority);
         }
     }
@@ -23,6 +27,58 @@ mod tests {
         assert_eq!(find_repeated_item_type("abcdec"), 'c');
     }
 
+    #[test]
+    fn test_plan_rucksack_moves() {
+        assert_eq!(plan_rucksack_moves("abcd"), vec![]);
+        let expected = vec![
+            Move {
+                item_type: 'a',
+                count: 1,
+                from: Compartment::Second,
+                to: Compartment::First,
+            },
+            Move {
+                item_type: 'b',
+                count: 1,
+                from: Compartment::First,
+                to: Compartment::Second,
+            },
+        ];
+        assert_eq!(plan_rucksack_moves("abaabb"), expected);
+    }
+
+    #[test]
+    fn test_assign_badge() {
+        let group = vec!["abc", "cde", "fcg"];
+        let expected = BadgeAssignment {
+            badge: 'c',
+            missing: vec![],
+        };
+        assert_eq!(assign_badge(&group), Some(expected));
+        let group = vec!["abc", "Bde", "fbg"];
+        let expected = BadgeAssignment {
+            badge: 'b',
+            missing: vec![1],
+        };
+        assert_eq!(assign_badge(&group), Some(expected));
+        assert_eq!(assign_badge(&["", "", ""]), None);
+    }
+
+    #[test]
+    fn test_plan_rebalancing() {
+        let fname = String::from("data/test_input");
+        let expected = "rucksack 1: move 1 p from second to first
+rucksack 2: move 2 L from second to first
+rucksack 3: move 1 P from second to first
+rucksack 4: move 2 v from second to first
+rucksack 5: move 2 t from second to first
+rucksack 6: move 1 s from second to first
+group 1: badge r
+group 2: badge Z
+";
+        assert_eq!(plan_rebalancing(&fname), expected);
+    }
+
     #[test]
     fn test_part1() {
         let fname = String::from("data/test_input");
@@ -41,27 +97,30 @@ mod tests {
 fn read_file(fname: &String) -> String {
     // Open file
     let path = Path::new(&fname);
-    let mut file = match File::open(&path) {
+    let mut file = match File::open(path) {
         Err(why) => panic!("couldn't open {}: {}", fname, why),
         Ok(file) => file,
     };
     // Parse file
     let mut data_str = String::new();
-    match file.read_to_string(&mut data_str) {
-        Err(why) => panic!("couldn't read {}: {}", fname, why),
-        Ok(_) => (),
+    if let Err(why) = file.read_to_string(&mut data_str) {
+        panic!("couldn't read {}: {}", fname, why)
     };
     data_str
 }
 
+fn split_compartments(rucksack: &str) -> (&str, &str) {
+    // Split the rucksack in its two compartiments
+    let len: usize = rucksack.len();
+    (&rucksack[..len / 2], &rucksack[len / 2..len])
+}
+
 fn find_repeated_item_type(rucksack: &str) -> char {
     // Find the repeated item type in the two compartiments of the rucksack
-    let len: usize = rucksack.len();
-    let first = &rucksack[..len / 2];
-    let second = &rucksack[len / 2..len];
+    let (first, second) = split_compartments(rucksack);
     for item in first.chars() {
         if second.contains(item) {
-            return item as char;
+            return item;
         }
     }
     panic!("No repeated item type was found in {}", rucksack);
@@ -70,9 +129,9 @@ fn find_repeated_item_type(rucksack: &str) -> char {
 fn compute_priority(item_type: &char) -> u32 {
     // Compute the priority of an item type
     if item_type.is_lowercase() {
-        return (*item_type as u32) - 96;
+        (*item_type as u32) - 96
     } else {
-        return (*item_type as u32) - 64 + 26;
+        (*item_type as u32) - 64 + 26
     }
 }
 
@@ -80,18 +139,155 @@ fn find_repeated_type_in_group(group: &Vec<&str>) -> char {
     // Find repeated item within group
     for item in group[0].chars() {
         if group[1].contains(item) & group[2].contains(item) {
-            return item as char;
+            return item;
         }
     }
     panic!("No repeated item type was found in {:?}", group);
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Compartment {
+    First,
+    Second,
+}
+
+impl Display for Compartment {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Compartment::First => write!(f, "first"),
+            Compartment::Second => write!(f, "second"),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+struct Move {
+    item_type: char,
+    count: usize,
+    from: Compartment,
+    to: Compartment,
+}
+
+impl Display for Move {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(
+            f,
+            "move {} {} from {} to {}",
+            self.count, self.item_type, self.from, self.to
+        )
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+struct BadgeAssignment {
+    badge: char,
+    missing: Vec<usize>, // members of the group (0, 1 or 2) that don't carry the badge yet
+}
+
+fn count_item_types(items: &str) -> BTreeMap<char, usize> {
+    // Count how many items of each type there are in a set of items
+    let mut counts = BTreeMap::new();
+    for item in items.chars() {
+        *counts.entry(item).or_insert(0) += 1;
+    }
+    counts
+}
+
+fn plan_rucksack_moves(rucksack: &str) -> Vec<Move> {
+    // Compute the moves needed so no item type appears in both compartiments
+    //
+    // Every item type that is present in both compartiments has to end up in only one of them,
+    // so the least number of moves is achieved by moving the items out of the compartiment that
+    // holds fewer of them. On ties, items are moved to the first compartiment.
+    let (first, second) = split_compartments(rucksack);
+    let first_counts = count_item_types(first);
+    let second_counts = count_item_types(second);
+    let mut moves = vec![];
+    for (item_type, n_first) in first_counts.iter() {
+        let n_second = match second_counts.get(item_type) {
+            Some(n) => n,
+            None => continue,
+        };
+        let movement = if n_first < n_second {
+            Move {
+                item_type: *item_type,
+                count: *n_first,
+                from: Compartment::First,
+                to: Compartment::Second,
+            }
+        } else {
+            Move {
+                item_type: *item_type,
+                count: *n_second,
+                from: Compartment::Second,
+                to: Compartment::First,
+            }
+        };
+        moves.push(movement);
+    }
+    moves
+}
+
+fn assign_badge(group: &[&str]) -> Option<BadgeAssignment> {
+    // Propose a badge for a group of elves
+    //
+    // The badge is the item type carried by the largest number of members of the group (the one
+    // carried by all of them in a valid group). On ties, the item type with the lowest priority
+    // is chosen. Members that don't carry the badge are listed as missing. Return None if the
+    // group carries no items at all.
+    let mut carriers: BTreeMap<char, usize> = BTreeMap::new();
+    for rucksack in group.iter() {
+        for item_type in count_item_types(rucksack).keys() {
+            *carriers.entry(*item_type).or_insert(0) += 1;
+        }
+    }
+    let (badge, _) = carriers.iter().max_by(|a, b| {
+        a.1.cmp(b.1)
+            .then(compute_priority(b.0).cmp(&compute_priority(a.0)))
+    })?;
+    let badge = *badge;
+    let missing = group
+        .iter()
+        .enumerate()
+        .filter(|(_, rucksack)| !rucksack.contains(badge))
+        .map(|(i, _)| i)
+        .collect();
+    Some(BadgeAssignment { badge, missing })
+}
+
+fn plan_rebalancing(fname: &String) -> String {
+    // Build the list of moves for every rucksack and the badge for every group of elves
+    let data = read_file(fname);
+    let mut plan = String::new();
+    let rucksacks: Vec<&str> = data.lines().collect();
+    for (i, rucksack) in rucksacks.iter().enumerate() {
+        for movement in plan_rucksack_moves(rucksack) {
+            plan.push_str(&format!("rucksack {}: {}\n", i + 1, movement));
+        }
+    }
+    for (i, group) in rucksacks.chunks(3).enumerate() {
+        let assignment = match assign_badge(group) {
+            Some(assignment) => assignment,
+            None => {
+                plan.push_str(&format!("group {}: no items to use as badge\n", i + 1));
+                continue;
+            }
+        };
+        plan.push_str(&format!("group {}: badge {}", i + 1, assignment.badge));
+        for member in assignment.missing.iter() {
+            plan.push_str(&format!(", give one to rucksack {}", 3 * i + member + 1));
+        }
+        plan.push('\n');
+    }
+    plan
+}
+
 fn solve_part1(fname: &String) -> u32 {
     // Read data file
-    let data = read_file(&fname);
+    let data = read_file(fname);
     let mut priorities: u32 = 0;
     for line in data.lines() {
-        let repeated_item_type = find_repeated_item_type(&line);
+        let repeated_item_type = find_repeated_item_type(line);
         priorities += compute_priority(&repeated_item_type);
     }
     priorities
@@ -99,13 +295,13 @@ fn solve_part1(fname: &String) -> u32 {
 
 fn solve_part2(fname: &String) -> u32 {
     // Read data file
-    let data = read_file(&fname);
+    let data = read_file(fname);
     let mut priorities: u32 = 0;
     let mut group = vec![];
     for (i, line) in data.lines().enumerate() {
         group.push(line);
         if i % 3 == 2 {
-            group.push(&line);
+            group.push(line);
             let repeated_item_type = find_repeated_type_in_group(&group);
             priorities += compute_priority(&repeated_item_type);
             group.clear();
@@ -117,6 +313,12 @@ fn solve_part2(fname: &String) -> u32 {
 fn main() {
     let fname = String::from("data/input");
 
+    // print the rebalancing plan if asked to
+    if env::args().nth(1).as_deref() == Some("plan") {
+        print!("{}", plan_rebalancing(&fname));
+        return;
+    }
+
     // part 1
     let result = solve_part1(&fname);
     println!("Solution to part 1: {}", result);
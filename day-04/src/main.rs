@@ -1,39 +1,141 @@
+use std::env;
 use std::fs::File;
 use std::io::Read;
 use std::path::Path;
 
 #[cfg(test)]
+#[allow(clippy::items_after_test_module)]
 mod tests {
     use super::*;
 
     #[test]
     fn test_parse_lines() {
         let line = String::from("1-2,3-4");
-        assert_eq!(parse_line(&line), vec![1, 2, 3, 4]);
+        let expected = Group {
+            assignments: vec![
+                Assignment { start: 1, end: 2 },
+                Assignment { start: 3, end: 4 },
+            ],
+        };
+        assert_eq!(parse_line(&line), expected);
+        let line = String::from("1-2,3-4,10-12");
+        assert_eq!(parse_line(&line).assignments.len(), 3);
     }
 
     #[test]
     fn test_check_fully_contained_pairs() {
-        let pairs: Vec<u32> = Vec::from([1, 2, 3, 4]);
-        assert_eq!(check_pairs_fully_contained(&pairs), false);
-        let pairs: Vec<u32> = Vec::from([1, 4, 3, 5]);
-        assert_eq!(check_pairs_fully_contained(&pairs), false);
-        let pairs: Vec<u32> = Vec::from([2, 6, 2, 4]);
-        assert_eq!(check_pairs_fully_contained(&pairs), true);
-        let pairs: Vec<u32> = Vec::from([8, 9, 6, 9]);
-        assert_eq!(check_pairs_fully_contained(&pairs), true);
+        assert!(!parse_line("1-2,3-4").is_fully_contained());
+        assert!(!parse_line("1-4,3-5").is_fully_contained());
+        assert!(parse_line("2-6,2-4").is_fully_contained());
+        assert!(parse_line("8-9,6-9").is_fully_contained());
+        assert!(!parse_line("1-2,3-4,5-6").is_fully_contained());
+        assert!(parse_line("1-2,3-7,5-6").is_fully_contained());
     }
 
     #[test]
     fn test_check_overlap_pairs() {
-        let pairs: Vec<u32> = Vec::from([1, 2, 3, 4]);
-        assert_eq!(check_pairs_overlap(&pairs), false);
-        let pairs: Vec<u32> = Vec::from([6, 8, 3, 4]);
-        assert_eq!(check_pairs_overlap(&pairs), false);
-        let pairs: Vec<u32> = Vec::from([1, 4, 4, 5]);
-        assert_eq!(check_pairs_overlap(&pairs), true);
-        let pairs: Vec<u32> = Vec::from([4, 5, 3, 5]);
-        assert_eq!(check_pairs_overlap(&pairs), true);
+        assert!(!parse_line("1-2,3-4").overlaps());
+        assert!(!parse_line("6-8,3-4").overlaps());
+        assert!(parse_line("1-4,4-5").overlaps());
+        assert!(parse_line("4-5,3-5").overlaps());
+        assert!(!parse_line("1-2,3-4,5-6").overlaps());
+        assert!(parse_line("1-2,3-4,4-6").overlaps());
+    }
+
+    #[test]
+    fn test_intersection_len() {
+        assert_eq!(parse_line("1-2,3-4").intersection_len(), 0);
+        assert_eq!(parse_line("1-4,4-5").intersection_len(), 1);
+        assert_eq!(parse_line("2-8,3-7").intersection_len(), 5);
+        assert_eq!(parse_line("2-8,3-7,6-9").intersection_len(), 2);
+        assert_eq!(parse_line("2-8,3-7,1-2").intersection_len(), 0);
+    }
+
+    #[test]
+    fn test_coverage_queries() {
+        let groups = parse_file(&read_file(&String::from("data/test_input")));
+        assert_eq!(
+            find_uncovered_sections(&groups),
+            vec![Assignment { start: 1, end: 1 }]
+        );
+        assert_eq!(get_max_coverage(&groups), 8);
+        assert_eq!(
+            find_groups_overlapping_section(&groups, &6),
+            vec![0, 2, 3, 4, 5]
+        );
+        assert_eq!(find_groups_overlapping_section(&groups, &1), vec![]);
+    }
+
+    #[test]
+    fn test_coverage_large_sections() {
+        let groups = parse_file("1-4000000000,5-7\n10-4294967295");
+        assert_eq!(get_max_coverage(&groups), 2);
+        assert_eq!(find_uncovered_sections(&groups), vec![]);
+        let groups = parse_file("4-6,9-4000000000");
+        assert_eq!(
+            find_uncovered_sections(&groups),
+            vec![
+                Assignment { start: 1, end: 3 },
+                Assignment { start: 7, end: 8 }
+            ]
+        );
+        assert_eq!(get_max_coverage(&groups), 1);
+        // Section 0 is reported if it's assigned
+        let groups = parse_file("0-0,0-2\n4-5");
+        assert_eq!(
+            get_coverage(&groups),
+            vec![
+                (Assignment { start: 0, end: 0 }, 2),
+                (Assignment { start: 1, end: 2 }, 1),
+                (Assignment { start: 3, end: 3 }, 0),
+                (Assignment { start: 4, end: 5 }, 1)
+            ]
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "start is after end")]
+    fn test_parse_reversed_range() {
+        parse_line("2-4,6-5");
+    }
+
+    #[test]
+    fn test_overlap_graph() {
+        let groups = parse_file("1-3,2-4\n4-6,8-9,9-10\n12-12");
+        let graph = OverlapGraph::build(&groups);
+        assert_eq!(graph.elves.len(), 6);
+        assert_eq!(graph.edges, vec![[0, 1], [1, 2], [3, 4]]);
+        assert_eq!(
+            graph.get_connected_components(),
+            vec![vec![0, 1, 2], vec![3, 4], vec![5]]
+        );
+        assert_eq!(
+            graph.get_maximal_cliques(),
+            vec![vec![0, 1], vec![1, 2], vec![3, 4], vec![5]]
+        );
+    }
+
+    #[test]
+    fn test_maximal_cliques() {
+        let groups = parse_file("1-5,2-6,3-3,5-8");
+        let graph = OverlapGraph::build(&groups);
+        assert_eq!(
+            graph.get_maximal_cliques(),
+            vec![vec![0, 1, 2], vec![0, 1, 3]]
+        );
+    }
+
+    #[test]
+    fn test_to_dot() {
+        let graph = OverlapGraph::build(&parse_file("1-3,2-4\n5-5"));
+        let expected = "graph overlaps {
+    0 [label=\"1.1: 1-3\"];
+    1 [label=\"1.2: 2-4\"];
+    2 [label=\"2.1: 5-5\"];
+    0 -- 1 [label=\"2\"];
+}
+";
+        assert_eq!(graph.to_dot(), expected);
     }
 
     #[test]
@@ -54,62 +156,323 @@ mod tests {
 fn read_file(fname: &String) -> String {
     // Open file
     let path = Path::new(&fname);
-    let mut file = match File::open(&path) {
+    let mut file = match File::open(path) {
         Err(why) => panic!("couldn't open {}: {}", fname, why),
         Ok(file) => file,
     };
     // Parse file
     let mut content = String::new();
-    match file.read_to_string(&mut content) {
-        Err(why) => panic!("couldn't read {}: {}", fname, why),
-        Ok(_) => (),
+    if let Err(why) = file.read_to_string(&mut content) {
+        panic!("couldn't read {}: {}", fname, why)
     };
     content
 }
 
-fn parse_line(line: &str) -> Vec<u32> {
-    let mut pairs: Vec<u32> = Vec::new();
-    for pair in line.split(',') {
-        for bound in pair.split('-') {
-            pairs.push(bound.parse().unwrap());
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Assignment {
+    start: u32,
+    end: u32,
+}
+
+impl Assignment {
+    fn parse(range: &str) -> Self {
+        // Parse a range of sections like "2-4"
+        let (start, end) = range.split_once('-').expect("Invalid range of sections");
+        let assignment = Assignment {
+            start: start.parse().unwrap(),
+            end: end.parse().unwrap(),
+        };
+        if assignment.start > assignment.end {
+            panic!("Invalid range of sections '{}': start is after end", range)
+        }
+        assignment
+    }
+
+    fn contains(&self, other: &Assignment) -> bool {
+        // Check if the other assignment is fully contained in this one
+        self.start <= other.start && other.end <= self.end
+    }
+
+    fn overlaps(&self, other: &Assignment) -> bool {
+        // Check if both assignments share at least one section
+        self.start <= other.end && other.start <= self.end
+    }
+
+    fn intersection_len(&self, other: &Assignment) -> u32 {
+        // Compute the number of sections shared by both assignments
+        if !self.overlaps(other) {
+            return 0;
         }
+        self.end.min(other.end) - self.start.max(other.start) + 1
+    }
+
+    fn contains_section(&self, section: &u32) -> bool {
+        // Check if the given section is part of the assignment
+        self.start <= *section && *section <= self.end
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+struct Group {
+    assignments: Vec<Assignment>,
+}
+
+impl Group {
+    fn is_fully_contained(&self) -> bool {
+        // Check if any assignment of the group fully contains another one
+        for (i, first) in self.assignments.iter().enumerate() {
+            for second in self.assignments[i + 1..].iter() {
+                if first.contains(second) || second.contains(first) {
+                    return true;
+                }
+            }
+        }
+        false
+    }
+
+    fn overlaps(&self) -> bool {
+        // Check if any two assignments of the group overlap
+        for (i, first) in self.assignments.iter().enumerate() {
+            if self.assignments[i + 1..].iter().any(|a| first.overlaps(a)) {
+                return true;
+            }
+        }
+        false
+    }
+
+    fn intersection_len(&self) -> u32 {
+        // Compute the number of sections shared by every assignment of the group
+        let start = self.assignments.iter().map(|a| a.start).max();
+        let end = self.assignments.iter().map(|a| a.end).min();
+        match (start, end) {
+            (Some(start), Some(end)) if start <= end => end - start + 1,
+            _ => 0,
+        }
+    }
+}
+
+fn parse_line(line: &str) -> Group {
+    Group {
+        assignments: line.split(',').map(Assignment::parse).collect(),
     }
-    pairs
 }
 
-fn check_pairs_fully_contained(pairs: &Vec<u32>) -> bool {
-    if (pairs[0] <= pairs[2]) & (pairs[1] >= pairs[3]) {
-        return true;
+fn parse_file(file_content: &str) -> Vec<Group> {
+    file_content.lines().map(parse_line).collect()
+}
+
+fn get_coverage(groups: &[Group]) -> Vec<(Assignment, u32)> {
+    // Return the number of elves assigned to each section
+    //
+    // Sections are grouped into consecutive ranges with the same number of elves,
+    // spanning from section 1 (or 0, if it's assigned) up to the last assigned one.
+    let mut events: Vec<(u64, i64)> = vec![];
+    for assignment in groups.iter().flat_map(|group| group.assignments.iter()) {
+        events.push((assignment.start as u64, 1));
+        events.push((assignment.end as u64 + 1, -1));
     }
-    if (pairs[2] <= pairs[0]) & (pairs[3] >= pairs[1]) {
-        return true;
+    events.sort();
+    // Sweep the sections, closing a range every time the coverage changes
+    let mut coverage = vec![];
+    let mut current: i64 = 0;
+    let mut range_start: u64 = events.first().map_or(1, |(section, _)| (*section).min(1));
+    for (section, change) in events {
+        if section > range_start {
+            let range = Assignment {
+                start: range_start as u32,
+                end: (section - 1) as u32,
+            };
+            coverage.push((range, current as u32));
+            range_start = section;
+        }
+        current += change;
     }
-    return false;
+    coverage
+}
+
+fn find_uncovered_sections(groups: &[Group]) -> Vec<Assignment> {
+    // Find the ranges of sections up to the last assigned one that are not assigned to any elf
+    get_coverage(groups)
+        .into_iter()
+        .filter(|(_, n_elves)| *n_elves == 0)
+        .map(|(range, _)| range)
+        .collect()
+}
+
+fn get_max_coverage(groups: &[Group]) -> u32 {
+    // Get the maximum number of elves assigned to the same section
+    get_coverage(groups)
+        .into_iter()
+        .map(|(_, n_elves)| n_elves)
+        .max()
+        .unwrap_or(0)
 }
 
-fn check_pairs_overlap(pairs: &Vec<u32>) -> bool {
-    if (pairs[0] <= pairs[2]) & (pairs[2] <= pairs[1]) {
-        return true;
+fn find_groups_overlapping_section(groups: &[Group], section: &u32) -> Vec<usize> {
+    // Return the indices of the groups in which any elf is assigned to the given section
+    groups
+        .iter()
+        .enumerate()
+        .filter(|(_, group)| {
+            group
+                .assignments
+                .iter()
+                .any(|a| a.contains_section(section))
+        })
+        .map(|(i, _)| i)
+        .collect()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Elf {
+    group: usize,  // index of the group (line) the elf belongs to
+    member: usize, // position of the elf in its group
+    assignment: Assignment,
+}
+
+#[derive(Debug)]
+struct OverlapGraph {
+    elves: Vec<Elf>,
+    edges: Vec<[usize; 2]>, // pairs of indices of elves whose assignments overlap
+}
+
+impl OverlapGraph {
+    fn build(groups: &[Group]) -> Self {
+        // Build the graph of overlapping assignments among every elf in the groups
+        let mut elves = vec![];
+        for (i, group) in groups.iter().enumerate() {
+            for (j, assignment) in group.assignments.iter().enumerate() {
+                elves.push(Elf {
+                    group: i,
+                    member: j,
+                    assignment: *assignment,
+                });
+            }
+        }
+        // Sort the elves by the start of their assignments, so we only need to check the
+        // following ones until they start after the end of the current assignment
+        let mut order: Vec<usize> = (0..elves.len()).collect();
+        order.sort_by_key(|i| elves[*i].assignment.start);
+        let mut edges = vec![];
+        for (k, i) in order.iter().enumerate() {
+            for j in order[k + 1..].iter() {
+                if elves[*j].assignment.start > elves[*i].assignment.end {
+                    break;
+                }
+                edges.push([*i.min(j), *i.max(j)]);
+            }
+        }
+        edges.sort();
+        OverlapGraph { elves, edges }
+    }
+
+    fn get_neighbours(&self) -> Vec<Vec<usize>> {
+        // Return the adjacency list of the graph
+        let mut neighbours = vec![vec![]; self.elves.len()];
+        for [i, j] in self.edges.iter() {
+            neighbours[*i].push(*j);
+            neighbours[*j].push(*i);
+        }
+        neighbours
+    }
+
+    fn get_connected_components(&self) -> Vec<Vec<usize>> {
+        // Return the sets of elves connected through overlapping assignments
+        let neighbours = self.get_neighbours();
+        let mut visited = vec![false; self.elves.len()];
+        let mut components = vec![];
+        for start in 0..self.elves.len() {
+            if visited[start] {
+                continue;
+            }
+            visited[start] = true;
+            let mut component = vec![];
+            let mut stack = vec![start];
+            while let Some(elf) = stack.pop() {
+                component.push(elf);
+                for neighbour in neighbours[elf].iter() {
+                    if !visited[*neighbour] {
+                        visited[*neighbour] = true;
+                        stack.push(*neighbour);
+                    }
+                }
+            }
+            component.sort();
+            components.push(component);
+        }
+        components
     }
-    if (pairs[0] <= pairs[3]) & (pairs[3] <= pairs[1]) {
-        return true;
+
+    fn get_maximal_cliques(&self) -> Vec<Vec<usize>> {
+        // Return the sets of elves that all overlap with each other and can't be extended
+        //
+        // Any set of pairwise overlapping ranges share at least one section, so we sweep the
+        // sections keeping track of the active assignments. The active set is a maximal clique
+        // every time an assignment ends right after new ones have started.
+        let mut events = vec![];
+        for (i, elf) in self.elves.iter().enumerate() {
+            // Starts are sorted before ends on the same section because ranges are inclusive
+            events.push((elf.assignment.start, 0, i));
+            events.push((elf.assignment.end, 1, i));
+        }
+        events.sort();
+        let mut active: Vec<usize> = vec![];
+        let mut started = false;
+        let mut cliques = vec![];
+        for (_, kind, elf) in events {
+            if kind == 0 {
+                active.push(elf);
+                started = true;
+            } else {
+                if started {
+                    let mut clique = active.clone();
+                    clique.sort();
+                    cliques.push(clique);
+                    started = false;
+                }
+                active.retain(|e| *e != elf);
+            }
+        }
+        cliques
     }
-    if (pairs[2] <= pairs[0]) & (pairs[0] <= pairs[3]) {
-        return true;
+
+    fn get_label(&self, elf: &usize) -> String {
+        // Label an elf as group.member (both starting from 1) followed by its assignment
+        let elf = &self.elves[*elf];
+        format!(
+            "{}.{}: {}-{}",
+            elf.group + 1,
+            elf.member + 1,
+            elf.assignment.start,
+            elf.assignment.end
+        )
     }
-    if (pairs[2] <= pairs[1]) & (pairs[1] <= pairs[3]) {
-        return true;
+
+    fn to_dot(&self) -> String {
+        // Export the graph in Graphviz DOT format
+        //
+        // Edges are labeled with the number of sections shared by both elves.
+        let mut dot = String::from("graph overlaps {\n");
+        for i in 0..self.elves.len() {
+            dot.push_str(&format!("    {} [label=\"{}\"];\n", i, self.get_label(&i)));
+        }
+        for [i, j] in self.edges.iter() {
+            let shared = self.elves[*i]
+                .assignment
+                .intersection_len(&self.elves[*j].assignment);
+            dot.push_str(&format!("    {} -- {} [label=\"{}\"];\n", i, j, shared));
+        }
+        dot.push_str("}\n");
+        dot
     }
-    return false;
 }
 
 fn solve_part1(fname: &String) -> u32 {
     // Read data file
-    let file_content = read_file(&fname);
+    let file_content = read_file(fname);
     let mut n_contained_pairs: u32 = 0;
-    for line in file_content.lines() {
-        let pairs = parse_line(&line);
-        if check_pairs_fully_contained(&pairs) {
+    for group in parse_file(&file_content) {
+        if group.is_fully_contained() {
             n_contained_pairs += 1;
         }
     }
@@ -118,20 +481,92 @@ fn solve_part1(fname: &String) -> u32 {
 
 fn solve_part2(fname: &String) -> u32 {
     // Read data file
-    let file_content = read_file(&fname);
+    let file_content = read_file(fname);
     let mut n_overlap_pairs: u32 = 0;
-    for line in file_content.lines() {
-        let pairs = parse_line(&line);
-        if check_pairs_overlap(&pairs) {
+    for group in parse_file(&file_content) {
+        if group.overlaps() {
             n_overlap_pairs += 1;
         }
     }
     n_overlap_pairs
 }
 
+fn print_coverage_report(fname: &String) {
+    // Print the aggregated coverage of the sections by all the elves
+    let groups = parse_file(&read_file(fname));
+    let uncovered: Vec<String> = find_uncovered_sections(&groups)
+        .iter()
+        .map(|range| match range.start == range.end {
+            true => range.start.to_string(),
+            false => format!("{}-{}", range.start, range.end),
+        })
+        .collect();
+    println!("Sections covered by no elf: {}", uncovered.join(","));
+    println!(
+        "Maximum simultaneous coverage: {}",
+        get_max_coverage(&groups)
+    );
+    let duplicated: u32 = groups.iter().map(|group| group.intersection_len()).sum();
+    println!("Sections assigned to a whole group: {}", duplicated);
+}
+
+fn print_groups_overlapping_section(fname: &String, section: &u32) {
+    // Print the groups (one per line) in which any elf cleans the given section
+    let file_content = read_file(fname);
+    let lines: Vec<&str> = file_content.lines().collect();
+    let groups = parse_file(&file_content);
+    for i in find_groups_overlapping_section(&groups, section) {
+        println!("{}: {}", i + 1, lines[i]);
+    }
+}
+
+fn print_teams(fname: &String) {
+    // Print the connected components and the maximal cliques of the overlap graph
+    let graph = OverlapGraph::build(&parse_file(&read_file(fname)));
+    let format_elves = |elves: &Vec<usize>| -> String {
+        let labels: Vec<String> = elves.iter().map(|e| graph.get_label(e)).collect();
+        labels.join(", ")
+    };
+    println!("Connected components:");
+    for component in graph.get_connected_components().iter() {
+        println!("  [{}]", format_elves(component));
+    }
+    println!("Maximal cliques:");
+    for clique in graph.get_maximal_cliques().iter().filter(|c| c.len() > 1) {
+        println!("  [{}]", format_elves(clique));
+    }
+}
+
 fn main() {
     let fname = String::from("data/input");
 
+    // answer coverage queries if asked to
+    let args: Vec<String> = env::args().collect();
+    match args.get(1).map(|arg| arg.as_str()) {
+        Some("coverage") => {
+            print_coverage_report(&fname);
+            return;
+        }
+        Some("section") => {
+            let section: u32 = args
+                .get(2)
+                .and_then(|section| section.parse().ok())
+                .expect("A valid section number should be passed");
+            print_groups_overlapping_section(&fname, &section);
+            return;
+        }
+        Some("teams") => {
+            print_teams(&fname);
+            return;
+        }
+        Some("dot") => {
+            let graph = OverlapGraph::build(&parse_file(&read_file(&fname)));
+            print!("{}", graph.to_dot());
+            return;
+        }
+        _ => (),
+    }
+
     // part 1
     let result = solve_part1(&fname);
     println!("Solution to part 1: {}", result);
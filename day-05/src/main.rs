@@ -1,11 +1,155 @@
+use std::collections::HashSet;
+use std::collections::VecDeque;
+use std::env;
+use std::fmt;
+use std::fmt::Display;
+use std::fmt::Formatter;
 use std::fs::File;
 use std::io::Read;
 use std::path::Path;
 
 #[cfg(test)]
+#[allow(clippy::items_after_test_module)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_parse_stacks() {
+        let file_content = read_file(&String::from("data/test_input"));
+        let stacks = Stacks::parse(split_file(&file_content).0).unwrap();
+        assert_eq!(stacks.labels, vec!["1", "2", "3"]);
+        assert_eq!(
+            stacks.crates,
+            vec![vec!["Z", "N"], vec!["M", "C", "D"], vec!["P"]]
+        );
+        assert_eq!(stacks.get_top_crates(), "NDP");
+    }
+
+    #[test]
+    fn test_parse_wide_stacks() {
+        let drawing = "[AB]      [E]\n[C]  [D]  [FG]  [H]\n 1    2    10    11";
+        let stacks = Stacks::parse(drawing).unwrap();
+        assert_eq!(stacks.labels, vec!["1", "2", "10", "11"]);
+        assert_eq!(
+            stacks.crates,
+            vec![vec!["C", "AB"], vec!["D"], vec!["FG", "E"], vec!["H"]]
+        );
+        assert!(Stacks::parse("[A\n 1").is_err());
+        assert!(Stacks::parse("    [A]\n 1").is_err());
+        assert!(Stacks::parse("").is_err());
+    }
+
+    #[test]
+    fn test_round_trip() {
+        for fname in ["data/test_input", "data/input"] {
+            let file_content = read_file(&String::from(fname));
+            let drawing = split_file(&file_content).0;
+            let stacks = Stacks::parse(drawing).unwrap();
+            assert_eq!(stacks.to_string(), format!("{}\n", drawing));
+            assert_eq!(Stacks::parse(&stacks.to_string()).unwrap(), stacks);
+        }
+        let drawing = "[AB]\n[C]  [DE]\n 1    10";
+        let stacks = Stacks::parse(drawing).unwrap();
+        assert_eq!(stacks.to_string(), format!("{}\n", drawing));
+        // Drawings keep their own layout
+        let drawing = "[AB]      [E]\n[C]  [D]  [FG]  [H]\n 1   2     10   11  ";
+        let mut stacks = Stacks::parse(drawing).unwrap();
+        assert_eq!(stacks.to_string(), format!("{}\n", drawing));
+        assert_eq!(Stacks::parse(&stacks.to_string()).unwrap(), stacks);
+        let movement = parse_move("move 1 from 10 to 1").unwrap();
+        CrateMover9000.apply(&mut stacks, &movement).unwrap();
+        let expected = "[E]\n[AB]\n[C]  [D]  [FG]  [H]\n 1   2     10   11  \n";
+        assert_eq!(stacks.to_string(), expected);
+    }
+
+    #[test]
+    fn test_parse_move() {
+        let expected = Move {
+            count: 3,
+            from: 1,
+            to: 10,
+        };
+        assert_eq!(parse_move("move 3 from 1 to 10"), Ok(expected));
+        assert_eq!(expected.to_string(), "move 3 from 1 to 10");
+        assert!(parse_move("move 3 from 1").is_err());
+        assert!(parse_move("move a from 1 to 2").is_err());
+    }
+
+    #[test]
+    fn test_cranes() {
+        let initial = Stacks::parse("[A]\n[B]\n[C]\n 1   2").unwrap();
+        let movement = parse_move("move 3 from 1 to 2").unwrap();
+        let expected = [
+            (get_crane("9000").unwrap(), vec!["A", "B", "C"]),
+            (get_crane("9001").unwrap(), vec!["C", "B", "A"]),
+            (get_crane("limited-2").unwrap(), vec!["B", "A", "C"]),
+        ];
+        for (crane, expected_stack) in expected {
+            let mut stacks = initial.clone();
+            crane.apply(&mut stacks, &movement).unwrap();
+            assert!(stacks.crates[0].is_empty());
+            assert_eq!(stacks.crates[1], expected_stack);
+        }
+        assert!(get_crane("limited-0").is_err());
+        assert!(get_crane("9002").is_err());
+    }
+
+    #[test]
+    fn test_invalid_moves() {
+        let initial = Stacks::parse("[A]\n 1   2").unwrap();
+        let mut stacks = initial.clone();
+        let movement = parse_move("move 2 from 1 to 2").unwrap();
+        assert!(CrateMover9000.apply(&mut stacks, &movement).is_err());
+        assert_eq!(stacks, initial);
+        let movement = parse_move("move 1 from 3 to 2").unwrap();
+        assert!(CrateMover9001.apply(&mut stacks, &movement).is_err());
+    }
+
+    #[test]
+    fn test_replay() {
+        let file_content = read_file(&String::from("data/test_input"));
+        let (drawing, moves) = split_file(&file_content);
+        let moves = parse_moves(moves).unwrap();
+        let states = replay(&Stacks::parse(drawing).unwrap(), &moves, &CrateMover9000).unwrap();
+        assert_eq!(states.len(), 5);
+        assert_eq!(
+            states[1].to_string(),
+            "[D]\n[N] [C]\n[Z] [M] [P]\n 1   2   3\n"
+        );
+        assert_eq!(states[4].get_top_crates(), "CMZ");
+        let moves = parse_moves("move 1 from 2 to 1\nmove 4 from 1 to 2").unwrap();
+        let result = replay(&Stacks::parse(drawing).unwrap(), &moves, &CrateMover9000);
+        assert!(result.unwrap_err().starts_with("Step 2"));
+    }
+
+    #[test]
+    fn test_plan_moves() {
+        let initial = Stacks::parse("[A]\n[B] [C]\n 1   2   3").unwrap();
+        let target = Stacks::parse("        [B]\n[C]     [A]\n 1   2   3").unwrap();
+        let moves = plan_moves(&initial, &target, &CrateMover9000, &1000).unwrap();
+        assert_eq!(moves.len(), 2);
+        let moves = plan_moves(&initial, &target, &CrateMover9001, &1000).unwrap();
+        assert_eq!(moves.len(), 3);
+        let states = replay(&initial, &moves, &CrateMover9001).unwrap();
+        assert_eq!(states.last().unwrap().crates, target.crates);
+        // Plans to unreachable or invalid targets
+        let other = Stacks::parse("[A]\n[B] [D]\n 1   2   3").unwrap();
+        assert!(plan_moves(&initial, &other, &CrateMover9000, &1000).is_err());
+        let target = Stacks::parse("[C]\n[A]\n[B]\n 1   2   3").unwrap();
+        assert!(plan_moves(&initial, &target, &CrateMover9000, &1).is_err());
+    }
+
+    #[test]
+    fn test_plan_test_input() {
+        let file_content = read_file(&String::from("data/test_input"));
+        let initial = Stacks::parse(split_file(&file_content).0).unwrap();
+        let target = simulate(&String::from("data/test_input"), &CrateMover9001).unwrap();
+        let moves = plan_moves(&initial, &target, &CrateMover9001, &100_000).unwrap();
+        assert!(moves.len() <= 4);
+        let states = replay(&initial, &moves, &CrateMover9001).unwrap();
+        assert_eq!(states.last().unwrap(), &target);
+    }
+
     #[test]
     fn test_part1() {
         let fname = String::from("data/test_input");
@@ -24,119 +168,441 @@ mod tests {
 fn read_file(fname: &String) -> String {
     // Open file
     let path = Path::new(&fname);
-    let mut file = match File::open(&path) {
+    let mut file = match File::open(path) {
         Err(why) => panic!("couldn't open {}: {}", fname, why),
         Ok(file) => file,
     };
     // Parse file
     let mut content = String::new();
-    match file.read_to_string(&mut content) {
-        Err(why) => panic!("couldn't read {}: {}", fname, why),
-        Ok(_) => (),
+    if let Err(why) = file.read_to_string(&mut content) {
+        panic!("couldn't read {}: {}", fname, why)
     };
     content
 }
 
-fn parse_location_of_crates(file_content: &str) -> Vec<Vec<char>> {
-    let n_crates = _get_number_of_crates(&file_content);
-    let mut crates: Vec<Vec<char>> = vec![[].to_vec(); n_crates as usize];
-    for line in file_content.lines() {
-        if line.chars().nth(1).expect("bla").is_digit(10) {
-            break;
-        }
-        for i in 0..(line.len() - 2) / 4 + 1 {
-            let char_index = 4 as usize * i as usize + 1;
-            let char = line.chars().nth(char_index).expect("bla");
-            if char.is_alphabetic() {
-                crates[i].insert(0, char)
-            };
-        }
-    }
-    crates
-}
-
-fn _get_number_of_crates(file_content: &str) -> u32 {
-    let mut n_crates: u32 = 0;
-    for line in file_content.lines() {
-        if !line.is_empty() {
-            if &line.trim()[0..1] == "1" {
-                n_crates = line
-                    .split_whitespace()
-                    .last()
-                    .unwrap()
-                    .parse()
-                    .expect("Failed to parse line");
-                break;
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Stacks {
+    labels: Vec<String>, // labels of the stacks, as written below them in the drawing
+    crates: Vec<Vec<String>>, // crates of every stack, from the bottom to the top
+    label_line: String,  // line with the labels, exactly as it was drawn
+    columns: Vec<usize>, // column of the drawing where the crates of every stack start
+}
+
+fn find_tokens(line: &[char], is_crate: bool) -> Vec<(usize, usize, String)> {
+    // Find the tokens in a line of the drawing along with their start and end positions
+    //
+    // If is_crate is true, tokens are crates enclosed in brackets and only the id of the crate is
+    // returned. Otherwise, tokens are separated by whitespaces.
+    let mut tokens = vec![];
+    let mut i = 0;
+    while i < line.len() {
+        if line[i].is_whitespace() {
+            i += 1;
+            continue;
+        }
+        let start = i;
+        if is_crate {
+            while i < line.len() && line[i] != ']' {
+                i += 1;
+            }
+            i += 1;
+            let token: String = line[start..i.min(line.len())].iter().collect();
+            tokens.push((start, i, token));
+        } else {
+            while i < line.len() && !line[i].is_whitespace() {
+                i += 1;
             }
+            tokens.push((start, i, line[start..i].iter().collect()));
         }
     }
-    n_crates
+    tokens
 }
 
-fn parse_move(instructions: &str) -> (u32, usize, usize) {
-    let moves = instructions.split_whitespace().collect::<Vec<&str>>();
-    (
-        moves[1].parse().expect("cannot parse"),
-        moves[3].parse().expect("cannot parse"),
-        moves[5].parse().expect("cannot parse"),
-    )
+impl Stacks {
+    fn parse(drawing: &str) -> Result<Self, String> {
+        // Parse the drawing of the stacks of crates
+        //
+        // The last line of the drawing contains the labels of the stacks. Every crate belongs to
+        // the stack whose label lies under it.
+        let lines: Vec<Vec<char>> = drawing
+            .lines()
+            .filter(|l| !l.trim().is_empty())
+            .map(|l| l.chars().collect())
+            .collect();
+        let (label_line, crate_lines) = match lines.split_last() {
+            Some(split) => split,
+            None => return Err(String::from("Empty drawing of stacks")),
+        };
+        let labels = find_tokens(label_line, false);
+        let mut crates: Vec<Vec<String>> = vec![vec![]; labels.len()];
+        // Crates are drawn where the bottom one of each stack is, or centered over the label of
+        // empty stacks
+        let mut columns: Vec<usize> = labels
+            .iter()
+            .map(|(start, end, _)| (start + (end - start) / 2).saturating_sub(1))
+            .collect();
+        for line in crate_lines.iter().rev() {
+            for (start, end, token) in find_tokens(line, true) {
+                if !(token.starts_with('[') && token.ends_with(']') && token.len() > 2) {
+                    return Err(format!("Invalid crate '{}'", token));
+                }
+                let stack = labels
+                    .iter()
+                    .position(|(l_start, l_end, _)| *l_start < end && start < *l_end)
+                    .ok_or(format!("Crate '{}' is not above any stack", token))?;
+                if crates[stack].is_empty() {
+                    columns[stack] = start;
+                }
+                crates[stack].push(token[1..token.len() - 1].to_string());
+            }
+        }
+        Ok(Stacks {
+            labels: labels.into_iter().map(|(_, _, label)| label).collect(),
+            crates,
+            label_line: label_line.iter().collect(),
+            columns,
+        })
+    }
+
+    fn get_index(&self, label: &usize) -> Result<usize, String> {
+        // Get the index of the stack with the given label
+        self.labels
+            .iter()
+            .position(|l| *l == label.to_string())
+            .ok_or(format!("There is no stack labeled {}", label))
+    }
+
+    fn get_top_crates(&self) -> String {
+        // Get the crates at the top of every stack (ignoring empty stacks)
+        self.crates
+            .iter()
+            .filter_map(|stack| stack.last())
+            .cloned()
+            .collect()
+    }
 }
 
-fn solve_part1(fname: &String) -> String {
-    // Read data file
-    let file_content = read_file(&fname);
-    let mut crates = parse_location_of_crates(&file_content);
-    let mut read_move = false;
-    for line in file_content.lines() {
-        if line.is_empty(){
-            read_move = true;
-            continue
+impl Display for Stacks {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        // Draw the stacks in the same layout they are parsed from
+        let height = self.crates.iter().map(|s| s.len()).max().unwrap_or(0);
+        for level in (0..height).rev() {
+            let mut line = String::new();
+            for (stack, column) in self.crates.iter().zip(self.columns.iter()) {
+                if let Some(id) = stack.get(level) {
+                    // Keep at least a space between crates that don't fit in their columns
+                    let width = line.chars().count();
+                    let start = match width {
+                        0 => *column,
+                        _ => (*column).max(width + 1),
+                    };
+                    line.push_str(&" ".repeat(start - width));
+                    line.push_str(&format!("[{}]", id));
+                }
+            }
+            writeln!(f, "{}", line)?;
+        }
+        writeln!(f, "{}", self.label_line)
+    }
+}
+
+fn split_file(file_content: &str) -> (&str, &str) {
+    // Split the content of the file into the drawing of the stacks and the moves
+    match file_content.split_once("\n\n") {
+        Some(split) => split,
+        None => (file_content, ""),
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Move {
+    count: usize, // number of crates to move
+    from: usize,  // label of the origin stack
+    to: usize,    // label of the destination stack
+}
+
+impl Display for Move {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "move {} from {} to {}", self.count, self.from, self.to)
+    }
+}
+
+fn parse_move(instructions: &str) -> Result<Move, String> {
+    // Parse a move like "move 1 from 2 to 1"
+    let words = instructions.split_whitespace().collect::<Vec<&str>>();
+    if words.len() != 6 || words[0] != "move" || words[2] != "from" || words[4] != "to" {
+        return Err(format!("Invalid move '{}'", instructions));
+    }
+    let parse_number = |word: &str| -> Result<usize, String> {
+        word.parse()
+            .map_err(|_| format!("Invalid number '{}' in move '{}'", word, instructions))
+    };
+    Ok(Move {
+        count: parse_number(words[1])?,
+        from: parse_number(words[3])?,
+        to: parse_number(words[5])?,
+    })
+}
+
+fn parse_moves(moves: &str) -> Result<Vec<Move>, String> {
+    moves.lines().map(parse_move).collect()
+}
+
+trait Crane {
+    // Maximum number of crates the crane can lift at once (keeping their order)
+    fn get_capacity(&self) -> usize;
+
+    fn apply(&self, stacks: &mut Stacks, movement: &Move) -> Result<(), String> {
+        // Move the crates between the stacks, lifting as many crates as possible each time
+        let from = stacks.get_index(&movement.from)?;
+        let to = stacks.get_index(&movement.to)?;
+        if stacks.crates[from].len() < movement.count {
+            return Err(format!(
+                "Cannot {}: stack {} has only {} crates",
+                movement,
+                movement.from,
+                stacks.crates[from].len()
+            ));
+        }
+        let mut remaining = movement.count;
+        while remaining > 0 {
+            let lift = remaining.min(self.get_capacity());
+            let height = stacks.crates[from].len();
+            let crates = stacks.crates[from].split_off(height - lift);
+            stacks.crates[to].extend(crates);
+            remaining -= lift;
+        }
+        Ok(())
+    }
+}
+
+struct CrateMover9000;
+
+struct CrateMover9001;
+
+struct LimitedCrane {
+    capacity: usize,
+}
+
+impl Crane for CrateMover9000 {
+    fn get_capacity(&self) -> usize {
+        1
+    }
+}
+
+impl Crane for CrateMover9001 {
+    fn get_capacity(&self) -> usize {
+        usize::MAX
+    }
+}
+
+impl Crane for LimitedCrane {
+    fn get_capacity(&self) -> usize {
+        self.capacity
+    }
+}
+
+fn get_crane(model: &str) -> Result<Box<dyn Crane>, String> {
+    // Get a crane from its model name: 9000, 9001 or limited-K (lifts up to K crates at once)
+    match model {
+        "9000" => Ok(Box::new(CrateMover9000)),
+        "9001" => Ok(Box::new(CrateMover9001)),
+        _ => match model.strip_prefix("limited-").map(|k| k.parse()) {
+            Some(Ok(capacity)) if capacity > 0 => Ok(Box::new(LimitedCrane { capacity })),
+            _ => Err(format!("Unknown crane model '{}'", model)),
+        },
+    }
+}
+
+fn replay(stacks: &Stacks, moves: &[Move], crane: &dyn Crane) -> Result<Vec<Stacks>, String> {
+    // Apply the moves and return every state of the stacks, starting with the initial one
+    let mut states = vec![stacks.clone()];
+    for (step, movement) in moves.iter().enumerate() {
+        let mut state = states.last().unwrap().clone();
+        crane
+            .apply(&mut state, movement)
+            .map_err(|why| format!("Step {}: {}", step + 1, why))?;
+        states.push(state);
+    }
+    Ok(states)
+}
+
+fn plan_moves(
+    initial: &Stacks,
+    target: &Stacks,
+    crane: &dyn Crane,
+    max_states: &usize,
+) -> Result<Vec<Move>, String> {
+    // Find a shortest sequence of moves that turns the initial stacks into the target ones
+    //
+    // Run a breadth-first search over the states of the stacks, where each move of any number of
+    // crates between two stacks is a single step. Give up after exploring max_states states.
+    if initial.labels != target.labels {
+        return Err(String::from(
+            "Initial and target stacks have different labels",
+        ));
+    }
+    let mut initial_crates: Vec<&String> = initial.crates.iter().flatten().collect();
+    let mut target_crates: Vec<&String> = target.crates.iter().flatten().collect();
+    initial_crates.sort();
+    target_crates.sort();
+    if initial_crates != target_crates {
+        return Err(String::from(
+            "Initial and target stacks have different crates",
+        ));
+    }
+    let labels = initial
+        .labels
+        .iter()
+        .map(|label| label.parse())
+        .collect::<Result<Vec<usize>, _>>()
+        .map_err(|_| String::from("Stacks should be labeled with numbers"))?;
+    // Store every explored state along with the previous state and the move that leads to it
+    let mut states: Vec<(Stacks, Option<(usize, Move)>)> = vec![(initial.clone(), None)];
+    let mut visited = HashSet::from([initial.crates.clone()]);
+    let mut queue = VecDeque::from([0]);
+    while let Some(current) = queue.pop_front() {
+        let state = states[current].0.clone();
+        // Only the arrangement of the crates matters, not the layout of the drawings
+        if state.crates == target.crates {
+            // Walk back the states to recover the moves
+            let mut moves = vec![];
+            let mut index = current;
+            while let Some((previous, movement)) = states[index].1 {
+                moves.push(movement);
+                index = previous;
+            }
+            moves.reverse();
+            return Ok(moves);
         }
-        if read_move {
-            let movements = parse_move(&line);
-            for _ in 0..movements.0 {
-                let tmp = crates[movements.1 - 1].pop().expect("Cannot pop crate");
-                crates[movements.2 - 1].push(tmp);
-            };
+        for (i, from) in state.crates.iter().enumerate() {
+            for (j, to) in labels.iter().enumerate() {
+                if i == j {
+                    continue;
+                }
+                for count in 1..=from.len() {
+                    let movement = Move {
+                        count,
+                        from: labels[i],
+                        to: *to,
+                    };
+                    let mut next = state.clone();
+                    crane.apply(&mut next, &movement)?;
+                    if visited.insert(next.crates.clone()) {
+                        states.push((next, Some((current, movement))));
+                        queue.push_back(states.len() - 1);
+                    }
+                }
+            }
+        }
+        if states.len() > *max_states {
+            return Err(format!(
+                "No plan found after exploring {} states",
+                max_states
+            ));
         }
     }
-    let mut message: Vec<char> = Vec::new();
-    for column in crates.iter() {
-        message.push(column.last().expect("no last element").clone())
+    Err(String::from("The target stacks cannot be reached"))
+}
+
+fn simulate(fname: &String, crane: &dyn Crane) -> Result<Stacks, String> {
+    // Read data file and return the final state of the stacks
+    let file_content = read_file(fname);
+    let (drawing, moves) = split_file(&file_content);
+    let mut stacks = Stacks::parse(drawing)?;
+    for (step, movement) in parse_moves(moves)?.iter().enumerate() {
+        crane
+            .apply(&mut stacks, movement)
+            .map_err(|why| format!("Step {}: {}", step + 1, why))?;
     }
-    message.iter().collect::<String>()
+    Ok(stacks)
+}
+
+fn solve_part1(fname: &String) -> String {
+    let stacks = simulate(fname, &CrateMover9000).expect("Failed to move the crates");
+    stacks.get_top_crates()
 }
 
 fn solve_part2(fname: &String) -> String {
-    // Read data file
-    let file_content = read_file(&fname);
-    let mut crates = parse_location_of_crates(&file_content);
-    let mut read_move = false;
-    for line in file_content.lines() {
-        if line.is_empty(){
-            read_move = true;
-            continue
+    let stacks = simulate(fname, &CrateMover9001).expect("Failed to move the crates");
+    stacks.get_top_crates()
+}
+
+fn print_replay(fname: &String, model: &str, step: &Option<usize>) -> Result<(), String> {
+    // Print the state of the stacks after the given step, or after every step if None
+    let file_content = read_file(fname);
+    let (drawing, moves) = split_file(&file_content);
+    let moves = parse_moves(moves)?;
+    let states = replay(&Stacks::parse(drawing)?, &moves, get_crane(model)?.as_ref())?;
+    match step {
+        Some(step) => {
+            let state = states
+                .get(*step)
+                .ok_or(format!("There are only {} steps", moves.len()))?;
+            print!("{}", state);
         }
-        if read_move {
-            let movements = parse_move(&line);
-            let mut tmp: Vec<char> = Vec::new();
-            for _ in 0..movements.0 {
-                tmp.push(crates[movements.1 - 1].pop().expect("Cannot pop crate"));
-            };
-            tmp.reverse();
-            crates[movements.2 - 1].extend(tmp);
+        None => {
+            for (i, state) in states.iter().enumerate() {
+                match i {
+                    0 => println!("initial state"),
+                    _ => println!("after step {}: {}", i, moves[i - 1]),
+                }
+                println!("{}", state);
+            }
         }
     }
-    let mut message: Vec<char> = Vec::new();
-    for column in crates.iter() {
-        message.push(column.last().expect("no last element").clone())
+    Ok(())
+}
+
+fn print_plan(model: &str, initial: &String, target: &String) -> Result<(), String> {
+    // Print a puzzle file with the initial stacks and the moves that lead to the target ones
+    let initial_content = read_file(initial);
+    let target_content = read_file(target);
+    let initial = Stacks::parse(split_file(&initial_content).0)?;
+    let target = Stacks::parse(split_file(&target_content).0)?;
+    let moves = plan_moves(&initial, &target, get_crane(model)?.as_ref(), &1_000_000)?;
+    println!("{}", initial);
+    for movement in moves.iter() {
+        println!("{}", movement);
     }
-    message.iter().collect::<String>()
+    Ok(())
 }
 
 fn main() {
     let fname = String::from("data/input");
 
+    // draw the initial stacks or replay the moves if asked to
+    let args: Vec<String> = env::args().collect();
+    match args.get(1).map(|arg| arg.as_str()) {
+        Some("draw") => {
+            let file_content = read_file(&fname);
+            let stacks = Stacks::parse(split_file(&file_content).0).expect("Invalid drawing");
+            print!("{}", stacks);
+            return;
+        }
+        Some("replay") => {
+            // cargo run -- replay <crane model> [step]
+            let model = args.get(2).map(|m| m.as_str()).unwrap_or("9000");
+            let step = args.get(3).map(|s| s.parse().expect("Invalid step"));
+            if let Err(why) = print_replay(&fname, model, &step) {
+                eprintln!("{}", why);
+            }
+            return;
+        }
+        Some("plan") => {
+            // cargo run -- plan <crane model> <initial stacks file> <target stacks file>
+            if args.len() != 5 {
+                eprintln!("Usage: plan <crane model> <initial stacks file> <target stacks file>");
+                return;
+            }
+            if let Err(why) = print_plan(&args[2], &args[3], &args[4]) {
+                eprintln!("{}", why);
+            }
+            return;
+        }
+        _ => (),
+    }
+
     // part 1
     let result = solve_part1(&fname);
     println!("Solution to part 1: {}", result);
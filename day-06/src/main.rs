@@ -1,21 +1,98 @@
+use std::collections::HashMap;
+use std::collections::VecDeque;
+use std::env;
 use std::fs::File;
+use std::hash::Hash;
+use std::io;
 use std::io::Read;
 use std::path::Path;
 
 #[cfg(test)]
+#[allow(clippy::items_after_test_module)]
 mod tests {
     use super::*;
+    use std::io::Write;
+    use std::net::{TcpListener, TcpStream};
+    use std::thread;
 
     #[test]
     fn test_check_unique_chars() {
         let signal = String::from("abcd");
-        assert_eq!(check_unique_chars(&signal), true);
+        assert_eq!(find_markers(&signal, 4), vec![4]);
         let signal = String::from("aacd");
-        assert_eq!(check_unique_chars(&signal), false);
+        assert_eq!(find_markers(&signal, 4), vec![]);
         let signal = String::from("abdd");
-        assert_eq!(check_unique_chars(&signal), false);
+        assert_eq!(find_markers(&signal, 4), vec![]);
         let signal = String::from("abca");
-        assert_eq!(check_unique_chars(&signal), false);
+        assert_eq!(find_markers(&signal, 4), vec![]);
+    }
+
+    #[test]
+    fn test_find_markers() {
+        let signal = String::from("mjqjpqmgbljsphdztnvjfqwrcgsmlb");
+        assert_eq!(find_markers(&signal, 4)[..3], [7, 8, 9]);
+        assert_eq!(find_markers(&signal, 14)[0], 19);
+        assert_eq!(find_markers(&signal, 1).len(), signal.len());
+        assert_eq!(find_markers(&signal, 0), vec![]);
+        assert_eq!(find_markers(&signal, 100), vec![]);
+        // Unicode characters are handled as single elements of the signal
+        assert_eq!(find_markers("ñaßß€ñ", 3), vec![3, 6]);
+        // Any kind of element works, like raw bytes
+        assert_eq!(find_markers_in(b"aabcc", &2), vec![3, 4]);
+    }
+
+    #[test]
+    fn test_marker_detector() {
+        let mut detector = MarkerDetector::new(&4, &14);
+        let signal = b"mjqjpqmgbljsphdztnvjfqwrcgsmlb";
+        let mut events = vec![];
+        // Feed the signal in small chunks
+        for chunk in signal.chunks(3) {
+            events.extend(detector.push(chunk));
+        }
+        events.extend(detector.finish());
+        assert_eq!(events[0], Event::StartOfPacket(7));
+        assert_eq!(events[1], Event::StartOfMessage(19));
+        assert_eq!(events[2], Event::Message(b"jfqwrcgsmlb".to_vec()));
+        assert_eq!(events.len(), 3);
+    }
+
+    #[test]
+    fn test_framed_messages() {
+        let mut detector = MarkerDetector::new(&2, &3);
+        let mut events = detector.push(b"aaabcxxy\nyzz");
+        events.extend(detector.push(b"zdefggg\n"));
+        events.extend(detector.finish());
+        let expected = [
+            Event::StartOfPacket(4),
+            Event::StartOfMessage(5),
+            Event::Message(b"xxyyzz".to_vec()),
+            Event::StartOfMessage(14),
+            Event::Message(b"fggg".to_vec()),
+        ];
+        assert_eq!(events, expected);
+    }
+
+    #[test]
+    fn test_read_from_socket() {
+        // Use a local socket as a stand-in for a live stream of data
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        let sender = thread::spawn(move || {
+            let mut stream = TcpStream::connect(address).unwrap();
+            for chunk in b"bvwbjplbgvbhsrlpgdmjqwftvncz\n".chunks(5) {
+                stream.write_all(chunk).unwrap();
+                stream.flush().unwrap();
+            }
+        });
+        let (mut stream, _) = listener.accept().unwrap();
+        let mut detector = MarkerDetector::new(&4, &14);
+        let mut events = vec![];
+        detector.read(&mut stream, &mut |e| events.push(e)).unwrap();
+        sender.join().unwrap();
+        assert_eq!(events[0], Event::StartOfPacket(5));
+        assert_eq!(events[1], Event::StartOfMessage(23));
+        assert_eq!(events[2], Event::Message(b"tvncz".to_vec()));
     }
 
     #[test]
@@ -36,76 +113,226 @@ mod tests {
 fn read_file(fname: &String) -> String {
     // Open file
     let path = Path::new(&fname);
-    let mut file = match File::open(&path) {
+    let mut file = match File::open(path) {
         Err(why) => panic!("couldn't open {}: {}", fname, why),
         Ok(file) => file,
     };
     // Parse file
     let mut content = String::new();
-    match file.read_to_string(&mut content) {
-        Err(why) => panic!("couldn't read {}: {}", fname, why),
-        Ok(_) => (),
+    if let Err(why) = file.read_to_string(&mut content) {
+        panic!("couldn't read {}: {}", fname, why)
     };
     content
 }
 
-fn check_unique_chars(subset: &str) -> bool {
-    for i in 0..subset.len() {
-        for j in i + 1..subset.len() {
-            if subset.chars().nth(i) == subset.chars().nth(j) {
-                return false;
+fn find_markers_in<T: Eq + Hash + Copy>(signal: &[T], window: &usize) -> Vec<usize> {
+    // Find every position in the signal where the last elements are all different
+    //
+    // A marker is found at position i (starting from 1) when the window of elements that ends in
+    // the i-th element has no repeated elements. Keep a table with the number of times each
+    // element is in the current window and the number of elements that are repeated in it, so
+    // every element of the signal is visited only twice.
+    let mut markers = vec![];
+    if *window == 0 {
+        return markers;
+    }
+    let mut counts: HashMap<T, usize> = HashMap::new();
+    let mut n_repeated: usize = 0;
+    for (i, element) in signal.iter().enumerate() {
+        // Add the new element to the window
+        let count = counts.entry(*element).or_insert(0);
+        *count += 1;
+        if *count == 2 {
+            n_repeated += 1;
+        }
+        // Remove the element that just left the window
+        if i >= *window {
+            let count = counts.get_mut(&signal[i - window]).unwrap();
+            *count -= 1;
+            if *count == 1 {
+                n_repeated -= 1;
             }
         }
+        if i + 1 >= *window && n_repeated == 0 {
+            markers.push(i + 1);
+        }
     }
-    true
+    markers
 }
 
-fn solve_part1(fname: &String) -> u32 {
+fn find_markers(signal: &str, window: usize) -> Vec<usize> {
+    // Find the positions of every marker in a signal made of (Unicode) characters
+    let signal: Vec<char> = signal.chars().collect();
+    find_markers_in(&signal, &window)
+}
+
+fn find_first_marker(fname: &String, window: usize) -> u32 {
     // Read data file
-    let file_content = read_file(&fname);
+    let file_content = read_file(fname);
     // Convert the signal into a vec
-    let signal = file_content.replace("\n", "");
-    // Find the marker position
-    let mut position: u32 = 0;
-    for i in (4 - 1)..signal.len() {
-        // Check if there are non-repeated characters in the current
-        // sequence of four elements in the signal
-        if check_unique_chars(&signal[i - (4 - 1)..i + 1]) {
-            // Found marker!
-            // (need to add 1 because in Rust indices start in zero)
-            position = i as u32 + 1;
-            break;
-        }
+    let signal = file_content.replace('\n', "");
+    // Return the position of the first marker
+    match find_markers(&signal, window).first() {
+        Some(position) => *position as u32,
+        None => 0,
     }
-    // Return marker position
-    position
+}
+
+fn solve_part1(fname: &String) -> u32 {
+    find_first_marker(fname, 4)
 }
 
 fn solve_part2(fname: &String) -> u32 {
-    // Read data file
-    let file_content = read_file(&fname);
-    // Convert the signal into a vec
-    let signal = file_content.replace("\n", "");
-    // Find the marker position
-    let mut position: u32 = 0;
-    for i in (14 - 1)..signal.len() {
-        // Check if there are non-repeated characters in the current
-        // sequence of four elements in the signal
-        if check_unique_chars(&signal[i - (14 - 1)..i + 1]) {
-            // Found marker!
-            // (need to add 1 because in Rust indices start in zero)
-            position = i as u32 + 1;
-            break;
+    find_first_marker(fname, 14)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Event {
+    StartOfPacket(usize),  // position of the start-of-packet marker
+    StartOfMessage(usize), // position of a start-of-message marker
+    Message(Vec<u8>),      // content of a message, between two start-of-message markers
+}
+
+#[derive(Debug)]
+struct Window {
+    size: usize,
+    elements: VecDeque<u8>, // elements currently in the window
+    counts: [usize; 256],   // number of times each byte is in the window
+    n_repeated: usize,      // number of bytes that are repeated in the window
+}
+
+impl Window {
+    fn new(size: &usize) -> Self {
+        Window {
+            size: *size,
+            elements: VecDeque::with_capacity(*size + 1),
+            counts: [0; 256],
+            n_repeated: 0,
         }
     }
-    // Return marker position
-    position
+
+    fn push(&mut self, byte: &u8) -> bool {
+        // Add a byte to the window and return true if the window is a marker
+        self.elements.push_back(*byte);
+        self.counts[*byte as usize] += 1;
+        if self.counts[*byte as usize] == 2 {
+            self.n_repeated += 1;
+        }
+        if self.elements.len() > self.size {
+            let old = self.elements.pop_front().unwrap() as usize;
+            self.counts[old] -= 1;
+            if self.counts[old] == 1 {
+                self.n_repeated -= 1;
+            }
+        }
+        self.size > 0 && self.elements.len() == self.size && self.n_repeated == 0
+    }
+
+    fn clear(&mut self) {
+        *self = Window::new(&self.size);
+    }
+}
+
+#[derive(Debug)]
+struct MarkerDetector {
+    packet_window: Window,
+    message_window: Window,
+    position: usize,          // number of bytes of the signal processed so far
+    found_packet: bool,       // whether the start-of-packet marker has been found
+    message: Option<Vec<u8>>, // content of the current message, if any has started
+}
+
+impl MarkerDetector {
+    fn new(packet_size: &usize, message_size: &usize) -> Self {
+        MarkerDetector {
+            packet_window: Window::new(packet_size),
+            message_window: Window::new(message_size),
+            position: 0,
+            found_packet: false,
+            message: None,
+        }
+    }
+
+    fn push(&mut self, chunk: &[u8]) -> Vec<Event> {
+        // Process a chunk of the signal and return the events found in it
+        //
+        // Messages start after every start-of-message marker and end right before the next one,
+        // whose window is searched from the start of the message. Line breaks are not part of
+        // the signal.
+        let mut events = vec![];
+        for byte in chunk.iter().filter(|b| **b != b'\n' && **b != b'\r') {
+            self.position += 1;
+            if !self.found_packet && self.packet_window.push(byte) {
+                self.found_packet = true;
+                events.push(Event::StartOfPacket(self.position));
+            }
+            if let Some(message) = self.message.as_mut() {
+                message.push(*byte);
+            }
+            if self.message_window.push(byte) {
+                if let Some(mut message) = self.message.take() {
+                    // The marker belongs to the next message
+                    message.truncate(message.len() - self.message_window.size);
+                    events.push(Event::Message(message));
+                }
+                events.push(Event::StartOfMessage(self.position));
+                self.message = Some(vec![]);
+                self.message_window.clear();
+            }
+        }
+        events
+    }
+
+    fn finish(&mut self) -> Vec<Event> {
+        // Flush the message that is still open at the end of the signal
+        match self.message.take() {
+            Some(message) => vec![Event::Message(message)],
+            None => vec![],
+        }
+    }
+
+    fn read<R: Read>(&mut self, reader: &mut R, on_event: &mut dyn FnMut(Event)) -> io::Result<()> {
+        // Read the whole signal from the reader, calling on_event as soon as events are found
+        let mut buffer = [0; 4096];
+        loop {
+            let n_bytes = match reader.read(&mut buffer) {
+                Ok(0) => break,
+                Ok(n_bytes) => n_bytes,
+                Err(why) if why.kind() == io::ErrorKind::Interrupted => continue,
+                Err(why) => return Err(why),
+            };
+            self.push(&buffer[..n_bytes])
+                .into_iter()
+                .for_each(&mut *on_event);
+        }
+        self.finish().into_iter().for_each(on_event);
+        Ok(())
+    }
+}
+
+fn decode_stream() {
+    // Decode the signal coming from stdin, printing the events as they are found
+    let mut detector = MarkerDetector::new(&4, &14);
+    let mut on_event = |event: Event| match event {
+        Event::StartOfPacket(position) => println!("start-of-packet at {}", position),
+        Event::StartOfMessage(position) => println!("start-of-message at {}", position),
+        Event::Message(message) => println!("message: {}", String::from_utf8_lossy(&message)),
+    };
+    if let Err(why) = detector.read(&mut io::stdin(), &mut on_event) {
+        panic!("couldn't read stdin: {}", why);
+    }
 }
 
 fn main() {
     let fname = String::from("data/input");
     // let fname = String::from("data/test_input");
 
+    // decode a signal from stdin if asked to (e.g. `cat data/input | cargo run -- stream`)
+    if env::args().nth(1).as_deref() == Some("stream") {
+        decode_stream();
+        return;
+    }
+
     // part 1
     let result = solve_part1(&fname);
     println!("Solution to part 1: {}", result);
@@ -1,45 +1,253 @@
 use std::collections::HashMap;
+use std::env;
 use std::fs::File;
 use std::io::Read;
 use std::path::Path;
 
 #[cfg(test)]
+#[allow(clippy::items_after_test_module)]
 mod tests {
     use super::*;
 
     #[test]
-    fn test_get_parent() {
-        let directory = String::from("/usr/share/lib");
-        assert_eq!(get_parent(&directory), String::from("/usr/share"));
-        let directory = String::from("/usr");
-        assert_eq!(get_parent(&directory), String::from("/"));
-        let directory = String::from("/a/b/c/d");
-        assert_eq!(get_parent(&directory), String::from("/a/b/c"));
-        let directory = String::from("/a");
-        assert_eq!(get_parent(&directory), String::from("/"));
+    fn test_parse_session() {
+        let fname = String::from("data/test_input");
+        let fs = parse_session(&read_file(&fname));
+        assert_eq!(fs.nodes.len(), 14);
+        let e = fs.lookup("/a/e").unwrap();
+        assert_eq!(fs.nodes[e].get_name(), "e");
+        assert_eq!(fs.nodes[e].get_size(), 584);
+        assert_eq!(fs.get_path(&e), "/a/e");
+        let a = fs.lookup("/a").unwrap();
+        assert_eq!(fs.nodes[a].get_size(), 94853);
+        assert_eq!(fs.nodes[e].get_parent(), Some(a));
+        assert_eq!(fs.get_path(&fs.lookup("/a/h.lst").unwrap()), "/a/h.lst");
+        assert_eq!(fs.nodes[fs.lookup("/d").unwrap()].get_size(), 24933642);
+        assert_eq!(fs.nodes[ROOT].get_size(), 48381165);
+        assert_eq!(fs.get_path(&ROOT), "/");
+        assert_eq!(fs.lookup("/"), Some(ROOT));
+        assert_eq!(fs.lookup("/a/x"), None);
+        assert_eq!(fs.lookup("/b.txt/x"), None);
     }
 
     #[test]
-    fn test_get_all_parents() {
-        let directory = String::from("/usr/share/lib");
-        let expected = vec![
-            String::from("/usr/share"),
-            String::from("/usr"),
-            String::from("/"),
-        ];
-        assert_eq!(get_all_parents(&directory), expected);
-        let directory = String::from("/");
-        let expected: Vec<&str> = vec![];
-        assert_eq!(get_all_parents(&directory), expected);
-        let directory = String::from("/a/b/c/d/e");
+    fn test_tokenize() {
+        assert_eq!(tokenize("$ cd /a/b"), Ok(Line::Cd("/a/b")));
+        assert_eq!(tokenize("$ ls"), Ok(Line::Ls));
+        assert_eq!(tokenize("dir e"), Ok(Line::Dir("e")));
+        assert_eq!(tokenize("584 i"), Ok(Line::File(584, "i")));
+        assert!(tokenize("$ cd").is_err());
+        assert!(tokenize("$ ls -l").is_err());
+        assert!(tokenize("$ rm x").is_err());
+        assert!(tokenize("12a i").is_err());
+        assert!(tokenize("dir a/b").is_err());
+        assert!(tokenize("$").is_err());
+        assert!(tokenize("x").is_err());
+    }
+
+    #[test]
+    fn test_parse_transcript() {
+        let transcript = "$ cd /a/b
+$ ls
+10 x
+dir c
+$ ls
+10 x
+$ cd ../../d/./e
+$ ls
+5 y
+$ cd /
+$ ls
+dir a
+$ cd ..
+$ cd a/b/c
+$ ls
+1 z";
+        let (fs, warnings) = parse_transcript(transcript).unwrap();
+        assert_eq!(fs.nodes[fs.lookup("/a/b").unwrap()].get_size(), 11);
+        assert_eq!(fs.nodes[fs.lookup("/d/e/y").unwrap()].get_size(), 5);
+        assert_eq!(fs.nodes[ROOT].get_size(), 16);
+        assert_eq!(fs.nodes.len(), 9);
+        assert_eq!(warnings, vec!["line 13: cannot go above /"]);
+    }
+
+    #[test]
+    fn test_parse_transcript_inconsistencies() {
+        let transcript = "$ cd /\n$ ls\n10 x\ndir y\n$ ls\n12 x\n5 y\ndir x";
+        let (fs, warnings) = parse_transcript(transcript).unwrap();
+        assert_eq!(fs.nodes[ROOT].get_size(), 10);
         let expected = vec![
-            String::from("/a/b/c/d"),
-            String::from("/a/b/c"),
-            String::from("/a/b"),
-            String::from("/a"),
-            String::from("/"),
+            "line 6: /x was listed with size 10, ignoring size 12",
+            "line 7: /y was listed as a directory",
+            "line 8: /x was listed as a file",
         ];
-        assert_eq!(get_all_parents(&directory), expected);
+        assert_eq!(warnings, expected);
+        let error = parse_transcript("$ cd /\n10 x").unwrap_err();
+        assert_eq!(error, "line 2: output found outside of ls");
+        let error = parse_transcript("$ ls\n10 x\n$ cd x").unwrap_err();
+        assert_eq!(error, "line 3: /x is not a directory");
+        assert!(parse_transcript("$ ls\n10").is_err());
+    }
+
+    #[test]
+    fn test_render_tree() {
+        let fs = parse_session(&read_file(&String::from("data/test_input")));
+        let expected = "/ (48381165)
+├── a/ (94853)
+│   ├── e/ (584)
+│   │   └── i (584)
+│   ├── f (29116)
+│   ├── g (2557)
+│   └── h.lst (62596)
+├── b.txt (14848514)
+├── c.dat (8504156)
+└── d/ (24933642)
+    ├── j (4060174)
+    ├── d.log (8033020)
+    ├── d.ext (5626152)
+    └── k (7214296)
+";
+        assert_eq!(fs.render_tree(), expected);
+    }
+
+    #[test]
+    fn test_disk_usage() {
+        let fs = parse_session(&read_file(&String::from("data/test_input")));
+        let usage: Vec<(String, String)> = fs
+            .get_disk_usage()
+            .iter()
+            .map(|(size, path)| (format_human_size(size), path.clone()))
+            .collect();
+        let expected: Vec<(String, String)> =
+            [("47M", "/"), ("24M", "/d"), ("93K", "/a"), ("584", "/a/e")]
+                .iter()
+                .map(|(size, path)| (size.to_string(), path.to_string()))
+                .collect();
+        assert_eq!(usage, expected);
+        assert_eq!(format_human_size(&1024), "1.0K");
+        assert_eq!(format_human_size(&1025), "1.1K");
+        assert_eq!(format_human_size(&(10 * 1024 * 1024 * 1024)), "10G");
+        assert_eq!(format_human_size(&1048575), "1.0M");
+        assert_eq!(format_human_size(&10239), "10K");
+    }
+
+    #[test]
+    fn test_matches_glob() {
+        let matches = |pattern: &str, name: &str| {
+            let pattern: Vec<char> = pattern.chars().collect();
+            let name: Vec<char> = name.chars().collect();
+            matches_glob(&pattern, &name)
+        };
+        assert!(matches("*", "b.txt"));
+        assert!(matches("*.txt", "b.txt"));
+        assert!(matches("?.*", "b.txt"));
+        assert!(matches("d*", "d"));
+        assert!(!matches("*.txt", "c.dat"));
+        assert!(!matches("?", "ab"));
+    }
+
+    #[test]
+    fn test_find() {
+        let fs = parse_session(&read_file(&String::from("data/test_input")));
+        let find = |args: &[&str]| -> Vec<String> {
+            let args: Vec<String> = args.iter().map(|arg| arg.to_string()).collect();
+            let query = FindQuery::parse(&args).unwrap();
+            fs.find(&query)
+                .iter()
+                .map(|node| fs.get_path(node))
+                .collect()
+        };
+        assert_eq!(find(&["-name", "d*"]), vec!["/d", "/d/d.log", "/d/d.ext"]);
+        assert_eq!(
+            find(&["-type", "d", "-mindepth", "1"]),
+            vec!["/a", "/a/e", "/d"]
+        );
+        assert_eq!(find(&["-type", "f", "-size", "+8000000"]).len(), 3);
+        assert_eq!(find(&["-size", "-1000"]), vec!["/a/e", "/a/e/i"]);
+        assert_eq!(find(&["-size", "584", "-maxdepth", "2"]), vec!["/a/e"]);
+        assert_eq!(find(&["-size", "-0"]), Vec::<String>::new());
+        assert_eq!(
+            find(&["-size", "+18446744073709551615"]),
+            Vec::<String>::new()
+        );
+        assert!(FindQuery::parse(&[String::from("-name")]).is_err());
+        assert!(FindQuery::parse(&[String::from("-foo"), String::from("1")]).is_err());
+    }
+
+    #[test]
+    fn test_to_json() {
+        let fs = parse_session("$ cd /\n$ ls\ndir a\n10 \"q\".txt\n$ cd a\n$ ls\n5 b");
+        let expected = concat!(
+            r#"{"name":"/","type":"dir","size":15,"children":["#,
+            r#"{"name":"a","type":"dir","size":5,"children":["#,
+            r#"{"name":"b","type":"file","size":5}]},"#,
+            r#"{"name":"\"q\".txt","type":"file","size":10}]}"#
+        );
+        assert_eq!(fs.to_json(&ROOT), expected);
+    }
+
+    #[test]
+    fn test_plan_cleanup() {
+        let session =
+            "$ cd /\n$ ls\ndir a\ndir b\n7 x\n$ cd a\n$ ls\n5 y\n4 z\n$ cd ..\n$ cd b\n$ ls\n6 w";
+        let fs = parse_session(session);
+        let plan = |required_space: u64, strategy: Strategy| -> Option<(Vec<String>, u64)> {
+            let plan = fs.plan_cleanup(&30, &required_space, &strategy)?;
+            let paths = plan.nodes.iter().map(|node| fs.get_path(node)).collect();
+            Some((paths, plan.freed))
+        };
+        assert_eq!(plan(8, Strategy::SmallestDirectory), Some((vec![], 0)));
+        assert_eq!(
+            plan(17, Strategy::SmallestDirectory),
+            Some((vec![String::from("/a")], 9))
+        );
+        assert_eq!(
+            plan(19, Strategy::SmallestDirectory),
+            Some((vec![String::from("/")], 22))
+        );
+        let expected = vec![String::from("/a"), String::from("/b")];
+        assert_eq!(plan(19, Strategy::Directories), Some((expected, 15)));
+        assert_eq!(plan(19, Strategy::Files).unwrap().1, 11);
+        assert_eq!(plan(19, Strategy::Any).unwrap().1, 11);
+        assert_eq!(plan(26, Strategy::Files).unwrap().1, 18);
+        assert_eq!(plan(26, Strategy::Directories).unwrap().1, 22);
+        assert_eq!(plan(31, Strategy::Any), None);
+        // Unreachable targets are rejected without sizing the search after them
+        assert_eq!(plan(300_000_000_000, Strategy::Files), None);
+        assert_eq!(plan(u64::MAX, Strategy::Any), None);
+        let fs = parse_session("$ cd /\n$ ls\ndir a\n7 x\n$ cd a\n$ ls\n5 y");
+        assert!(fs.plan_cleanup(&12, &12, &Strategy::Files).is_some());
+        assert!(fs.plan_cleanup(&12, &13, &Strategy::Files).is_none());
+        // Large sizes are planned without allocating tables after them
+        let fs = parse_session("$ cd /\n$ ls\ndir a\n600000000000 x\n$ cd a\n$ ls\n500000000000 y");
+        let plan = fs
+            .plan_cleanup(&1_100_000_000_000, &550_000_000_000, &Strategy::Files)
+            .unwrap();
+        assert_eq!(plan.freed, 600_000_000_000);
+        assert_eq!(plan.nodes, vec![fs.lookup("/x").unwrap()]);
+        let plan = fs
+            .plan_cleanup(&1_100_000_000_000, &1_100_000_000_000, &Strategy::Any)
+            .unwrap();
+        assert_eq!(plan.freed, 1_100_000_000_000);
+        let plan = fs
+            .plan_cleanup(&1_100_000_000_000, &1_000_000_000_000, &Strategy::Files)
+            .unwrap();
+        assert_eq!(plan.freed, 1_100_000_000_000);
+        assert_eq!(plan.nodes.len(), 2);
+    }
+
+    #[test]
+    fn test_plan_cleanup_test_input() {
+        let fs = parse_session(&read_file(&String::from("data/test_input")));
+        let plan = fs
+            .plan_cleanup(&70_000_000, &30_000_000, &Strategy::Files)
+            .unwrap();
+        assert_eq!(plan.freed, 8504156);
+        assert_eq!(plan.nodes, vec![fs.lookup("/c.dat").unwrap()]);
+        let plan = fs
+            .plan_cleanup(&70_000_000, &30_000_000, &Strategy::Directories)
+            .unwrap();
+        assert_eq!(plan.freed, 24933642);
     }
 
     #[test]
@@ -60,140 +268,931 @@ mod tests {
 fn read_file(fname: &String) -> String {
     // Open file
     let path = Path::new(&fname);
-    let mut file = match File::open(&path) {
+    let mut file = match File::open(path) {
         Err(why) => panic!("couldn't open {}: {}", fname, why),
         Ok(file) => file,
     };
     // Parse file
     let mut content = String::new();
-    match file.read_to_string(&mut content) {
-        Err(why) => panic!("couldn't read {}: {}", fname, why),
-        Ok(_) => (),
+    if let Err(why) = file.read_to_string(&mut content) {
+        panic!("couldn't read {}: {}", fname, why)
     };
     content
 }
 
-fn get_parent(directory: &str) -> &str {
-    // Return the path to the parent dir of the given directory
-    let mut index: usize = 0;
-    for (i, c) in directory.chars().enumerate() {
-        if c == '/' {
-            index = i;
+const ROOT: usize = 0;
+// Largest needed size for which the sums of sizes are stored as bitsets when planning a cleanup
+const MAX_BITSET_SIZE: u64 = 1 << 24;
+
+#[derive(Debug)]
+enum Node {
+    Dir {
+        name: String,
+        parent: Option<usize>, // index of the parent directory (None for the root)
+        children: Vec<usize>,  // indices of the files and directories inside it
+        size: u64,             // total size of the directory, including its subdirectories
+    },
+    File {
+        name: String,
+        parent: usize,
+        size: u64,
+    },
+}
+
+impl Node {
+    fn get_name(&self) -> &str {
+        match self {
+            Node::Dir { name, .. } | Node::File { name, .. } => name,
+        }
+    }
+
+    fn get_size(&self) -> u64 {
+        match self {
+            Node::Dir { size, .. } | Node::File { size, .. } => *size,
         }
     }
-    // If the parent directory is /, return a slice with the first character
-    if index == 0 {
-        return &directory[..1];
+
+    fn get_parent(&self) -> Option<usize> {
+        match self {
+            Node::Dir { parent, .. } => *parent,
+            Node::File { parent, .. } => Some(*parent),
+        }
+    }
+
+    fn get_children(&self) -> &[usize] {
+        match self {
+            Node::Dir { children, .. } => children,
+            Node::File { .. } => &[],
+        }
+    }
+
+    fn is_dir(&self) -> bool {
+        matches!(self, Node::Dir { .. })
     }
-    return &directory[..index];
 }
 
-fn get_all_parents(cwd: &str) -> Vec<&str> {
-    // Return all parents dir of cwd
-    let mut parents: Vec<&str> = Vec::new();
-    let mut parent = cwd;
-    while parent != String::from("/") {
-        parent = get_parent(&parent);
-        parents.push(parent)
+#[derive(Debug)]
+struct FileSystem {
+    nodes: Vec<Node>, // arena with every node of the tree, the root is the first one
+}
+
+impl FileSystem {
+    fn new() -> Self {
+        let root = Node::Dir {
+            name: String::from("/"),
+            parent: None,
+            children: vec![],
+            size: 0,
+        };
+        FileSystem { nodes: vec![root] }
+    }
+
+    fn get_child(&self, dir: &usize, name: &str) -> Option<usize> {
+        // Get the index of the child of a directory with the given name
+        self.nodes[*dir]
+            .get_children()
+            .iter()
+            .find(|child| self.nodes[**child].get_name() == name)
+            .copied()
+    }
+
+    fn add_node(&mut self, dir: &usize, node: Node) -> usize {
+        // Add a node to the arena and register it as child of the directory
+        let index = self.nodes.len();
+        self.nodes.push(node);
+        if let Node::Dir { children, .. } = &mut self.nodes[*dir] {
+            children.push(index);
+        }
+        index
+    }
+
+    fn add_dir(&mut self, dir: &usize, name: &str) -> usize {
+        // Add a directory inside another one, unless it already exists
+        if let Some(child) = self.get_child(dir, name) {
+            return child;
+        }
+        let node = Node::Dir {
+            name: String::from(name),
+            parent: Some(*dir),
+            children: vec![],
+            size: 0,
+        };
+        self.add_node(dir, node)
+    }
+
+    fn add_file(&mut self, dir: &usize, name: &str, size: &u64) -> usize {
+        // Add a file inside a directory, unless it already exists
+        if let Some(child) = self.get_child(dir, name) {
+            return child;
+        }
+        let node = Node::File {
+            name: String::from(name),
+            parent: *dir,
+            size: *size,
+        };
+        self.add_node(dir, node)
+    }
+
+    fn compute_sizes(&mut self) {
+        // Compute the size of every directory from the bottom of the tree up
+        //
+        // Children are always added after their parents, so going through the arena backwards
+        // visits every node after all of its descendants.
+        for node in self.nodes.iter_mut() {
+            if let Node::Dir { size, .. } = node {
+                *size = 0;
+            }
+        }
+        for i in (1..self.nodes.len()).rev() {
+            let node_size = self.nodes[i].get_size();
+            let parent = self.nodes[i].get_parent().unwrap();
+            if let Node::Dir { size, .. } = &mut self.nodes[parent] {
+                *size += node_size;
+            }
+        }
+    }
+
+    fn lookup(&self, path: &str) -> Option<usize> {
+        // Get the index of the node with the given absolute path
+        let mut node = ROOT;
+        for name in path.split('/').filter(|name| !name.is_empty()) {
+            node = self.get_child(&node, name)?;
+        }
+        Some(node)
+    }
+
+    fn get_path(&self, node: &usize) -> String {
+        // Get the absolute path of a node
+        let mut names = vec![];
+        let mut current = *node;
+        while let Some(parent) = self.nodes[current].get_parent() {
+            names.push(self.nodes[current].get_name());
+            current = parent;
+        }
+        names.reverse();
+        format!("/{}", names.join("/"))
+    }
+
+    fn get_dirs(&self) -> impl Iterator<Item = &Node> {
+        self.nodes.iter().filter(|node| node.is_dir())
     }
-    parents
 }
 
-fn get_size_of_directories(file_content: &str) -> HashMap<String, u32> {
-    // Return a hasmap with the size of every directory in the tree
+fn format_human_size(size: &u64) -> String {
+    // Format a size like `du -h` does (powers of 1024, rounding up)
+    let units = ["K", "M", "G", "T", "P"];
+    if *size < 1024 {
+        return size.to_string();
+    }
+    let mut value = *size as f64 / 1024.0;
+    let mut unit = 0;
+    loop {
+        // Round up first, so values that round to 1024 move to the next unit
+        let mut rounded = (value * 10.0).ceil() / 10.0;
+        if rounded >= 10.0 {
+            rounded = value.ceil();
+        }
+        if rounded < 1024.0 || unit == units.len() - 1 {
+            return match rounded < 10.0 {
+                true => format!("{:.1}{}", rounded, units[unit]),
+                false => format!("{:.0}{}", rounded, units[unit]),
+            };
+        }
+        value /= 1024.0;
+        unit += 1;
+    }
+}
+
+fn matches_glob(pattern: &[char], name: &[char]) -> bool {
+    // Check if a name matches a glob pattern with * and ? wildcards
+    match (pattern.first(), name.first()) {
+        (None, None) => true,
+        (Some('*'), _) => {
+            matches_glob(&pattern[1..], name)
+                || (!name.is_empty() && matches_glob(pattern, &name[1..]))
+        }
+        (Some('?'), Some(_)) => matches_glob(&pattern[1..], &name[1..]),
+        (Some(p), Some(n)) if p == n => matches_glob(&pattern[1..], &name[1..]),
+        _ => false,
+    }
+}
+
+fn escape_json(string: &str) -> String {
+    // Escape a string so it can be written inside quotes in a JSON file
+    let mut escaped = String::new();
+    for c in string.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\t' => escaped.push_str("\\t"),
+            c if (c as u32) < 0x20 => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[derive(Debug, Default)]
+struct FindQuery {
+    name: Option<String>, // glob pattern that the name of the node should match
+    is_dir: Option<bool>, // look only for directories (true) or files (false)
+    size: Option<u64>,
+    larger_than: Option<u64>, // look only for nodes strictly larger than this size
+    smaller_than: Option<u64>, // look only for nodes strictly smaller than this size
+    min_depth: Option<usize>, // the root has depth 0
+    max_depth: Option<usize>,
+}
+
+impl FindQuery {
+    fn parse(args: &[String]) -> Result<Self, String> {
+        // Parse arguments in the style of `find`
+        //
+        // Supported arguments: -name <glob>, -type <d|f>, -size <+N|-N|N> (larger than, smaller
+        // than or equal to N), -mindepth <N> and -maxdepth <N>.
+        let mut query = FindQuery::default();
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            let value = args
+                .next()
+                .ok_or(format!("Missing value for argument '{}'", arg))?;
+            let parse_number = |value: &str| -> Result<u64, String> {
+                value
+                    .parse()
+                    .map_err(|_| format!("Invalid number '{}' for argument '{}'", value, arg))
+            };
+            match arg.as_str() {
+                "-name" => query.name = Some(value.clone()),
+                "-type" => match value.as_str() {
+                    "d" => query.is_dir = Some(true),
+                    "f" => query.is_dir = Some(false),
+                    _ => return Err(format!("Invalid type '{}'", value)),
+                },
+                "-size" => {
+                    if let Some(size) = value.strip_prefix('+') {
+                        query.larger_than = Some(parse_number(size)?);
+                    } else if let Some(size) = value.strip_prefix('-') {
+                        query.smaller_than = Some(parse_number(size)?);
+                    } else {
+                        query.size = Some(parse_number(value)?);
+                    }
+                }
+                "-mindepth" => query.min_depth = Some(parse_number(value)? as usize),
+                "-maxdepth" => query.max_depth = Some(parse_number(value)? as usize),
+                _ => return Err(format!("Unknown argument '{}'", arg)),
+            }
+        }
+        Ok(query)
+    }
+
+    fn matches(&self, node: &Node, depth: &usize) -> bool {
+        // Check if a node at the given depth satisfies every condition of the query
+        if let Some(pattern) = &self.name {
+            let pattern: Vec<char> = pattern.chars().collect();
+            let name: Vec<char> = node.get_name().chars().collect();
+            if !matches_glob(&pattern, &name) {
+                return false;
+            }
+        }
+        self.is_dir.is_none_or(|is_dir| node.is_dir() == is_dir)
+            && self.size.is_none_or(|size| node.get_size() == size)
+            && self.larger_than.is_none_or(|size| node.get_size() > size)
+            && self.smaller_than.is_none_or(|size| node.get_size() < size)
+            && self.min_depth.is_none_or(|min_depth| *depth >= min_depth)
+            && self.max_depth.is_none_or(|max_depth| *depth <= max_depth)
+    }
+}
+
+impl FileSystem {
+    fn walk(&self) -> Vec<(usize, usize)> {
+        // Return every node of the tree along with its depth, in depth-first order
+        let mut nodes = vec![];
+        let mut stack = vec![(ROOT, 0)];
+        while let Some((node, depth)) = stack.pop() {
+            nodes.push((node, depth));
+            for child in self.nodes[node].get_children().iter().rev() {
+                stack.push((*child, depth + 1));
+            }
+        }
+        nodes
+    }
+
+    fn render_tree(&self) -> String {
+        // Draw the tree of directories and files like the `tree` command, along with their sizes
+        let mut output = format!("/ ({})\n", self.nodes[ROOT].get_size());
+        self.render_children(&ROOT, "", &mut output);
+        output
+    }
+
+    fn render_children(&self, dir: &usize, prefix: &str, output: &mut String) {
+        let children = self.nodes[*dir].get_children();
+        for (i, child) in children.iter().enumerate() {
+            let is_last = i == children.len() - 1;
+            let node = &self.nodes[*child];
+            let (branch, indent) = match is_last {
+                true => ("└── ", "    "),
+                false => ("├── ", "│   "),
+            };
+            let slash = if node.is_dir() { "/" } else { "" };
+            output.push_str(&format!(
+                "{}{}{}{} ({})\n",
+                prefix,
+                branch,
+                node.get_name(),
+                slash,
+                node.get_size()
+            ));
+            if node.is_dir() {
+                self.render_children(child, &format!("{}{}", prefix, indent), output);
+            }
+        }
+    }
+
+    fn get_disk_usage(&self) -> Vec<(u64, String)> {
+        // Return the size and path of every directory, sorted from the largest to the smallest
+        let mut usage: Vec<(u64, String)> = (0..self.nodes.len())
+            .filter(|node| self.nodes[*node].is_dir())
+            .map(|node| (self.nodes[node].get_size(), self.get_path(&node)))
+            .collect();
+        usage.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(&b.1)));
+        usage
+    }
+
+    fn find(&self, query: &FindQuery) -> Vec<usize> {
+        // Return the nodes that match the query, in depth-first order
+        self.walk()
+            .into_iter()
+            .filter(|(node, depth)| query.matches(&self.nodes[*node], depth))
+            .map(|(node, _)| node)
+            .collect()
+    }
+
+    fn to_json(&self, node: &usize) -> String {
+        // Export the tree that starts at the given node as JSON
+        let node = &self.nodes[*node];
+        let kind = if node.is_dir() { "dir" } else { "file" };
+        let mut json = format!(
+            "{{\"name\":\"{}\",\"type\":\"{}\",\"size\":{}",
+            escape_json(node.get_name()),
+            kind,
+            node.get_size()
+        );
+        if node.is_dir() {
+            let children: Vec<String> = node
+                .get_children()
+                .iter()
+                .map(|child| self.to_json(child))
+                .collect();
+            json.push_str(&format!(",\"children\":[{}]", children.join(",")));
+        }
+        json.push('}');
+        json
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum Line<'a> {
+    Cd(&'a str),        // $ cd <path>
+    Ls,                 // $ ls
+    Dir(&'a str),       // dir <name>
+    File(u64, &'a str), // <size> <name>
+}
+
+fn tokenize(line: &str) -> Result<Line<'_>, String> {
+    // Split a line of the transcript into a command or an entry of the output of ls
+    let words: Vec<&str> = line.split_whitespace().collect();
+    let check_name = |name: &'_ str| -> Result<(), String> {
+        match name.contains('/') || name == "." || name == ".." {
+            true => Err(format!("Invalid name '{}'", name)),
+            false => Ok(()),
+        }
+    };
+    match words[..] {
+        ["$", "cd", path] => Ok(Line::Cd(path)),
+        ["$", "ls"] => Ok(Line::Ls),
+        ["$", "cd"] => Err(String::from("Missing path for cd")),
+        ["$", "ls", ..] => Err(String::from("Arguments for ls are not supported")),
+        ["$", command, ..] => Err(format!("Unknown command '{}'", command)),
+        ["dir", name] => {
+            check_name(name)?;
+            Ok(Line::Dir(name))
+        }
+        [size, name] => {
+            check_name(name)?;
+            let size = size
+                .parse()
+                .map_err(|_| format!("Invalid file size '{}'", size))?;
+            Ok(Line::File(size, name))
+        }
+        _ => Err(format!("Invalid line '{}'", line)),
+    }
+}
+
+fn parse_transcript(file_content: &str) -> Result<(FileSystem, Vec<String>), String> {
+    // Build the tree of files and directories from the transcript of the terminal session
     //
-    // Define a hashmap for the size of the directories
-    let mut directories: HashMap<String, u32> = HashMap::new();
-    // Define a variable to store the current directory
-    let mut cwd = String::new();
-    // Parse the input file into a hashmap that contain the size of each dir
-    for line in file_content.lines() {
-        // Ignore lines that run ls
-        if line == "$ ls" {
+    // Paths passed to cd can be absolute or relative and have several components. Directories
+    // that weren't listed before are created when entering them, and running ls several times
+    // in the same directory doesn't duplicate its content. Inconsistencies in the transcript are
+    // returned as warnings, while invalid lines are errors.
+    let mut fs = FileSystem::new();
+    let mut warnings = vec![];
+    let mut cwd = ROOT;
+    let mut listing = false;
+    for (i, line) in file_content.lines().enumerate() {
+        if line.trim().is_empty() {
             continue;
-        };
-        // Change cwd if line runs the cd command
-        if line[0..4] == String::from("$ cd") {
-            let new_dir = line.split_whitespace().last().unwrap();
-            if new_dir == String::from("..") {
-                cwd = get_parent(&cwd).to_owned();
-            } else {
-                if new_dir == String::from("/") {
-                    cwd = String::from("/");
-                } else if cwd == String::from("/") {
-                    cwd = format!("/{}", new_dir);
-                } else {
-                    cwd = format!("{}/{}", cwd, new_dir);
+        }
+        let mut warn = |message: String| warnings.push(format!("line {}: {}", i + 1, message));
+        let error = |message: String| format!("line {}: {}", i + 1, message);
+        match tokenize(line).map_err(error)? {
+            Line::Cd(path) => {
+                listing = false;
+                if path.starts_with('/') {
+                    cwd = ROOT;
                 }
-                if !directories.contains_key(&cwd) {
-                    directories.insert(cwd.clone(), 0);
+                for name in path.split('/').filter(|n| !n.is_empty() && *n != ".") {
+                    if name == ".." {
+                        match fs.nodes[cwd].get_parent() {
+                            Some(parent) => cwd = parent,
+                            None => warn(String::from("cannot go above /")),
+                        }
+                        continue;
+                    }
+                    let child = fs.add_dir(&cwd, name);
+                    if !fs.nodes[child].is_dir() {
+                        return Err(error(format!("{} is not a directory", fs.get_path(&child))));
+                    }
+                    cwd = child;
                 }
             }
-            continue;
-        };
+            Line::Ls => listing = true,
+            _ if !listing => return Err(error(String::from("output found outside of ls"))),
+            Line::Dir(name) => {
+                let child = fs.add_dir(&cwd, name);
+                if !fs.nodes[child].is_dir() {
+                    warn(format!("{} was listed as a file", fs.get_path(&child)));
+                }
+            }
+            Line::File(size, name) => match fs.get_child(&cwd, name) {
+                Some(child) if fs.nodes[child].is_dir() => {
+                    warn(format!("{} was listed as a directory", fs.get_path(&child)));
+                }
+                Some(child) if fs.nodes[child].get_size() != size => warn(format!(
+                    "{} was listed with size {}, ignoring size {}",
+                    fs.get_path(&child),
+                    fs.nodes[child].get_size(),
+                    size
+                )),
+                Some(_) => (),
+                None => {
+                    fs.add_file(&cwd, name, &size);
+                }
+            },
+        }
+    }
+    fs.compute_sizes();
+    Ok((fs, warnings))
+}
 
-        // Read stdout lines
-        if line[0..3] == String::from("dir") {
-            continue;
+fn parse_session(file_content: &str) -> FileSystem {
+    // Build the tree of files and directories, printing the warnings found in the transcript
+    let (fs, warnings) = parse_transcript(file_content).unwrap_or_else(|why| panic!("{}", why));
+    for warning in warnings.iter() {
+        eprintln!("warning: {}", warning);
+    }
+    fs
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Strategy {
+    SmallestDirectory, // delete the single smallest directory that frees enough space
+    Directories,       // delete a set of directories (none inside another one)
+    Files,             // delete a set of files
+    Any,               // delete a set of files and directories (none inside another one)
+}
+
+impl Strategy {
+    fn parse(name: &str) -> Result<Self, String> {
+        match name {
+            "smallest-dir" => Ok(Strategy::SmallestDirectory),
+            "dirs" => Ok(Strategy::Directories),
+            "files" => Ok(Strategy::Files),
+            "any" => Ok(Strategy::Any),
+            _ => Err(format!("Unknown cleanup strategy '{}'", name)),
         }
+    }
 
-        // Add file size to cwd in directories hashmap
-        let file_size: u32 = line.split_whitespace().nth(0).unwrap().parse().unwrap();
-        directories
-            .entry(cwd.clone())
-            .and_modify(|s| *s += file_size);
-        // And add it to every parent of cwd
-        for parent in get_all_parents(&cwd).iter() {
-            directories
-                .entry(String::from(*parent).clone())
-                .and_modify(|s| *s += file_size);
+    fn can_delete(&self, node: &Node) -> bool {
+        match self {
+            Strategy::SmallestDirectory | Strategy::Directories => node.is_dir(),
+            Strategy::Files => !node.is_dir(),
+            Strategy::Any => true,
         }
     }
-    directories
 }
 
-fn solve_part1(fname: &String) -> u32 {
-    // Read data file
-    let file_content = read_file(&fname);
-    // Get size of directories
-    let directories = get_size_of_directories(&file_content);
-    // Compute the sum of all directories sizes at most as 100000
-    let mut result: u32 = 0;
-    for (_, size) in directories.iter() {
-        if *size <= 100000 as u32 {
-            result += size
+#[derive(Debug, PartialEq, Eq)]
+struct CleanupPlan {
+    nodes: Vec<usize>, // nodes to delete
+    freed: u64,        // total size of the deleted nodes
+}
+
+fn find_first_set_bit(bits: &[u64], start: &usize) -> Option<usize> {
+    // Find the first bit set at or after the given position
+    let mut word = start / 64;
+    if word >= bits.len() {
+        return None;
+    }
+    let mut current = bits[word] & (u64::MAX << (start % 64));
+    loop {
+        if current != 0 {
+            return Some(word * 64 + current.trailing_zeros() as usize);
+        }
+        word += 1;
+        if word >= bits.len() {
+            return None;
+        }
+        current = bits[word];
+    }
+}
+
+fn shift_into(source: &[u64], shift: &usize, n_bits: &usize, target: &mut [u64]) -> Vec<usize> {
+    // Add the bits of the source shifted by the given amount to the target
+    //
+    // Bits shifted beyond n_bits are dropped. Return the positions of the bits that weren't
+    // already set in the target.
+    let (word_shift, bit_shift) = (shift / 64, shift % 64);
+    let mut new_bits = vec![];
+    for j in word_shift..target.len() {
+        let mut shifted = source[j - word_shift] << bit_shift;
+        if bit_shift > 0 && j > word_shift {
+            shifted |= source[j - word_shift - 1] >> (64 - bit_shift);
+        }
+        if j == target.len() - 1 && !n_bits.is_multiple_of(64) {
+            shifted &= (1 << (n_bits % 64)) - 1;
+        }
+        let mut new = shifted & !target[j];
+        while new != 0 {
+            new_bits.push(j * 64 + new.trailing_zeros() as usize);
+            new &= new - 1;
+        }
+        target[j] |= shifted;
+    }
+    new_bits
+}
+
+trait SumSet: Clone {
+    // Set of the sums of sizes below a limit
+    type Origins; // node that first reached each sum
+
+    fn with_zero(limit: &u64) -> Self;
+    fn first_at_least(&self, sum: &u64) -> Option<u64>;
+    // Add the sums of the other set shifted by the given amount, returning the ones that are new
+    fn add_shifted(&mut self, other: &Self, shift: &u64) -> Vec<u64>;
+    fn remove_below(&mut self, sum: &u64);
+    fn new_origins(limit: &u64) -> Self::Origins;
+    fn set_origin(origins: &mut Self::Origins, sum: &u64, node: &usize);
+    fn get_origin(origins: &Self::Origins, sum: &u64) -> Option<usize>;
+}
+
+#[derive(Debug, Clone)]
+struct BitSums {
+    bits: Vec<u64>,
+    n_bits: usize,
+}
+
+impl SumSet for BitSums {
+    type Origins = Vec<u32>;
+
+    fn with_zero(limit: &u64) -> Self {
+        let n_bits = *limit as usize;
+        let mut bits = vec![0; n_bits.div_ceil(64)];
+        bits[0] = 1;
+        BitSums { bits, n_bits }
+    }
+
+    fn first_at_least(&self, sum: &u64) -> Option<u64> {
+        find_first_set_bit(&self.bits, &(*sum as usize)).map(|bit| bit as u64)
+    }
+
+    fn add_shifted(&mut self, other: &Self, shift: &u64) -> Vec<u64> {
+        let shift = (*shift).min(self.n_bits as u64) as usize;
+        shift_into(&other.bits, &shift, &self.n_bits, &mut self.bits)
+            .into_iter()
+            .map(|bit| bit as u64)
+            .collect()
+    }
+
+    fn remove_below(&mut self, sum: &u64) {
+        let sum = (*sum).min(self.n_bits as u64) as usize;
+        for word in self.bits[..sum / 64].iter_mut() {
+            *word = 0;
+        }
+        if let Some(word) = self.bits.get_mut(sum / 64) {
+            *word &= u64::MAX << (sum % 64);
+        }
+    }
+
+    fn new_origins(limit: &u64) -> Self::Origins {
+        vec![u32::MAX; *limit as usize]
+    }
+
+    fn set_origin(origins: &mut Self::Origins, sum: &u64, node: &usize) {
+        if origins[*sum as usize] == u32::MAX {
+            origins[*sum as usize] = *node as u32;
+        }
+    }
+
+    fn get_origin(origins: &Self::Origins, sum: &u64) -> Option<usize> {
+        match origins.get(*sum as usize) {
+            Some(&node) if node != u32::MAX => Some(node as usize),
+            _ => None,
         }
     }
-    result
 }
 
-fn solve_part2(fname: &String) -> u32 {
+#[derive(Debug, Clone)]
+struct SortedSums {
+    sums: Vec<u64>,
+    limit: u64,
+}
+
+impl SumSet for SortedSums {
+    type Origins = HashMap<u64, usize>;
+
+    fn with_zero(limit: &u64) -> Self {
+        SortedSums {
+            sums: vec![0],
+            limit: *limit,
+        }
+    }
+
+    fn first_at_least(&self, sum: &u64) -> Option<u64> {
+        let position = self.sums.partition_point(|s| s < sum);
+        self.sums.get(position).copied()
+    }
+
+    fn add_shifted(&mut self, other: &Self, shift: &u64) -> Vec<u64> {
+        let mut merged = Vec::with_capacity(self.sums.len() + other.sums.len());
+        let mut new_sums = vec![];
+        let mut kept = self.sums.iter().peekable();
+        for sum in other.sums.iter().map(|sum| sum + shift) {
+            if sum >= self.limit {
+                break;
+            }
+            while let Some(old) = kept.next_if(|old| **old < sum) {
+                merged.push(*old);
+            }
+            if kept.next_if_eq(&&sum).is_none() {
+                new_sums.push(sum);
+            }
+            merged.push(sum);
+        }
+        merged.extend(kept);
+        self.sums = merged;
+        new_sums
+    }
+
+    fn remove_below(&mut self, sum: &u64) {
+        let position = self.sums.partition_point(|s| s < sum);
+        self.sums.drain(..position);
+    }
+
+    fn new_origins(_limit: &u64) -> Self::Origins {
+        HashMap::new()
+    }
+
+    fn set_origin(origins: &mut Self::Origins, sum: &u64, node: &usize) {
+        origins.entry(*sum).or_insert(*node);
+    }
+
+    fn get_origin(origins: &Self::Origins, sum: &u64) -> Option<usize> {
+        origins.get(sum).copied()
+    }
+}
+
+impl FileSystem {
+    fn plan_cleanup(
+        &self,
+        disk_size: &u64,
+        required_space: &u64,
+        strategy: &Strategy,
+    ) -> Option<CleanupPlan> {
+        // Plan which nodes to delete to have at least the required free space in the disk
+        //
+        // Return None if there's no way to free enough space with the given strategy.
+        let used = self.nodes[ROOT].get_size();
+        let needed = used
+            .saturating_add(*required_space)
+            .saturating_sub(*disk_size);
+        if needed == 0 {
+            return Some(CleanupPlan {
+                nodes: vec![],
+                freed: 0,
+            });
+        }
+        if needed > used {
+            return None;
+        }
+        if *strategy == Strategy::SmallestDirectory {
+            return (0..self.nodes.len())
+                .filter(|node| self.nodes[*node].is_dir())
+                .filter(|node| self.nodes[*node].get_size() >= needed)
+                .min_by_key(|node| self.nodes[*node].get_size())
+                .map(|node| CleanupPlan {
+                    nodes: vec![node],
+                    freed: self.nodes[node].get_size(),
+                });
+        }
+        self.plan_minimal_cleanup(&needed, strategy)
+    }
+
+    fn plan_minimal_cleanup(&self, needed: &u64, strategy: &Strategy) -> Option<CleanupPlan> {
+        // Find the set of nodes (none inside another one) with the smallest total size that is
+        // at least the needed one
+        //
+        // Go through the nodes in depth-first order, where the descendants of a node are the
+        // ones right after it. Let the sums of sizes that can be freed by deleting nodes from the
+        // i-th onwards be sums[i]. Then sums[i] contains sums[i + 1] (if the node is not deleted)
+        // and sums[end(i)] shifted by the size of the node (if it's deleted, skipping all of its
+        // descendants). Only the distinct sums below the needed size are stored, keeping track of
+        // the node that first reached each sum so the plan can be recovered. Sums that reach the
+        // needed size are candidates for the plan, we only keep the smallest one.
+        let order: Vec<(usize, usize)> = self.walk();
+        let n_nodes = order.len();
+        // Find the position in the order where the descendants of every node end
+        let mut end = vec![n_nodes; n_nodes];
+        let mut open: Vec<usize> = vec![];
+        for (i, (_, depth)) in order.iter().enumerate() {
+            while let Some(last) = open.last() {
+                if order[*last].1 < *depth {
+                    break;
+                }
+                end[*last] = i;
+                open.pop();
+            }
+            open.push(i);
+        }
+        // Nothing can free more than the nodes that can be deleted without being inside another
+        // deletable node. Keep that upper bound for the nodes before the i-th one, so sums that
+        // can't reach the needed size anymore are dropped.
+        let mut bound = vec![0; n_nodes + 1];
+        let mut covered_until = 0;
+        for i in 0..n_nodes {
+            bound[i + 1] = bound[i];
+            if i >= covered_until && strategy.can_delete(&self.nodes[order[i].0]) {
+                bound[i + 1] += self.nodes[order[i].0].get_size();
+                covered_until = end[i];
+            }
+        }
+        if *needed > bound[n_nodes] {
+            return None;
+        }
+        // Dense sums are cheaper to store as bitsets, but those grow with the needed size
+        let best = match *needed <= MAX_BITSET_SIZE {
+            true => self.find_smallest_sum::<BitSums>(&order, &end, &bound, needed, strategy),
+            false => self.find_smallest_sum::<SortedSums>(&order, &end, &bound, needed, strategy),
+        };
+        let (freed, mut nodes) = best?;
+        nodes.sort();
+        Some(CleanupPlan { nodes, freed })
+    }
+
+    fn find_smallest_sum<S: SumSet>(
+        &self,
+        order: &[(usize, usize)],
+        end: &[usize],
+        bound: &[u64],
+        needed: &u64,
+        strategy: &Strategy,
+    ) -> Option<(u64, Vec<usize>)> {
+        // Find the smallest sum of sizes that is at least the needed one along with the nodes
+        // that add up to it (see plan_minimal_cleanup)
+        let n_nodes = order.len();
+        // Count how many times each set of sums will be needed, so it can be freed afterwards
+        let mut uses = vec![1; n_nodes + 1];
+        for i in 0..n_nodes {
+            if strategy.can_delete(&self.nodes[order[i].0]) {
+                uses[end[i]] += 1;
+            }
+        }
+        let mut sums: HashMap<usize, S> = HashMap::new();
+        sums.insert(n_nodes, S::with_zero(needed));
+        let mut reached_by = S::new_origins(needed);
+        // Smallest candidate: (total size, position of the deleted node, sum of the rest)
+        let mut best: Option<(u64, usize, u64)> = None;
+        for i in (0..n_nodes).rev() {
+            let mut current = match uses[i + 1] {
+                1 => sums.remove(&(i + 1)).unwrap(),
+                _ => sums[&(i + 1)].clone(),
+            };
+            uses[i + 1] -= 1;
+            let node = &self.nodes[order[i].0];
+            if strategy.can_delete(node) {
+                let size = node.get_size();
+                let rest = &sums[&end[i]];
+                if let Some(rest_size) = rest.first_at_least(&needed.saturating_sub(size)) {
+                    let total = rest_size + size;
+                    if best.is_none_or(|(best_total, _, _)| total < best_total) {
+                        best = Some((total, i, rest_size));
+                    }
+                }
+                for sum in current.add_shifted(rest, &size) {
+                    S::set_origin(&mut reached_by, &sum, &i);
+                }
+                uses[end[i]] -= 1;
+                if uses[end[i]] == 0 {
+                    sums.remove(&end[i]);
+                }
+            }
+            current.remove_below(&needed.saturating_sub(bound[i]));
+            sums.insert(i, current);
+        }
+        // Recover the nodes that lead to the best candidate
+        let (total, position, mut rest) = best?;
+        let mut nodes = vec![order[position].0];
+        while rest > 0 {
+            let position = S::get_origin(&reached_by, &rest)?;
+            nodes.push(order[position].0);
+            rest -= self.nodes[order[position].0].get_size();
+        }
+        Some((total, nodes))
+    }
+}
+
+fn solve_part1(fname: &String) -> u64 {
+    // Read data file
+    let file_content = read_file(fname);
+    // Build the tree of directories
+    let fs = parse_session(&file_content);
+    // Compute the sum of all directories sizes at most as 100000
+    fs.get_dirs()
+        .map(|dir| dir.get_size())
+        .filter(|size| *size <= 100000)
+        .sum()
+}
+
+fn solve_part2(fname: &String) -> u64 {
     // Read data file
-    let file_content = read_file(&fname);
-    // Get size of directories
-    let directories = get_size_of_directories(&file_content);
-    // Define varibales for total size of the drive, the required space for the update and the
-    // current size of the root.
-    let total_size: u32 = 70_000_000;
-    let required_space: u32 = 30_000_000;
-    let size_of_root = directories.get(&String::from("/")).unwrap();
-    // Calculate the minimum size that the directory should have in order to be a candidate for
-    // deletion
-    let min_size = size_of_root + required_space - total_size;
-    // Find the smallest directory that we can delete to free enough space
-    let mut result: u32 = total_size;
-    for (_, size) in directories.iter() {
-        if *size >= min_size && *size < result {
-            result = *size
-        }
-    }
-    result
+    let file_content = read_file(fname);
+    // Build the tree of directories
+    let fs = parse_session(&file_content);
+    // Find the smallest directory that we can delete to free enough space for the update
+    let plan = fs
+        .plan_cleanup(&70_000_000, &30_000_000, &Strategy::SmallestDirectory)
+        .expect("No directory is large enough");
+    plan.freed
 }
 
 fn main() {
     let fname = String::from("data/input");
     // let fname = String::from("data/test_input");
 
+    // inspect the filesystem if asked to
+    let args: Vec<String> = env::args().collect();
+    if args.len() > 1 {
+        let fs = parse_session(&read_file(&fname));
+        match args[1].as_str() {
+            "size" if args.len() == 3 => match fs.lookup(&args[2]) {
+                Some(node) => println!("{} {}", fs.nodes[node].get_size(), fs.get_path(&node)),
+                None => eprintln!("{}: no such file or directory", args[2]),
+            },
+            "tree" => print!("{}", fs.render_tree()),
+            "du" => {
+                for (size, path) in fs.get_disk_usage() {
+                    println!("{}\t{}", format_human_size(&size), path);
+                }
+            }
+            "find" => match FindQuery::parse(&args[2..]) {
+                Ok(query) => {
+                    for node in fs.find(&query) {
+                        println!("{}", fs.get_path(&node));
+                    }
+                }
+                Err(why) => eprintln!("{}", why),
+            },
+            "json" => println!("{}", fs.to_json(&ROOT)),
+            "cleanup" if args.len() == 5 => {
+                // cargo run -- cleanup <disk size> <required space> <strategy>
+                let disk_size: u64 = args[2].parse().expect("Invalid disk size");
+                let required_space: u64 = args[3].parse().expect("Invalid required space");
+                let strategy = Strategy::parse(&args[4]).unwrap_or_else(|why| panic!("{}", why));
+                match fs.plan_cleanup(&disk_size, &required_space, &strategy) {
+                    Some(plan) => {
+                        for node in plan.nodes.iter() {
+                            println!("rm -r {}", fs.get_path(node));
+                        }
+                        println!("# frees {}", plan.freed);
+                    }
+                    None => eprintln!("Not enough space can be freed"),
+                }
+            }
+            _ => eprintln!("Unknown command '{}'", args[1]),
+        }
+        return;
+    }
+
     // part 1
     let result = solve_part1(&fname);
     println!("Solution to part 1: {}", result);
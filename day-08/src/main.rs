@@ -1,61 +1,165 @@
+use std::collections::BTreeSet;
+use std::env;
 use std::fs::File;
 use std::io::Read;
 use std::path::Path;
 
 #[cfg(test)]
+#[allow(clippy::items_after_test_module)]
 mod tests {
     use super::*;
 
     #[test]
     fn test_is_visible_from_right() {
         let fname = String::from("data/test_input");
-        let forest = parse_file_to_2d_array(&read_file(&fname));
-        assert_eq!(is_visible_from_right(&forest, &1, &1), false);
-        assert_eq!(is_visible_from_right(&forest, &1, &2), true);
+        let forest = parse_forest(&read_file(&fname));
+        let visible = scan_lines(&forest, &Direction::Right).0;
+        assert!(!visible.get(&1, &1));
+        assert!(visible.get(&1, &2));
     }
 
     #[test]
     fn test_is_visible_from_left() {
         let fname = String::from("data/test_input");
-        let forest = parse_file_to_2d_array(&read_file(&fname));
-        assert_eq!(is_visible_from_left(&forest, &2, &2), false);
-        assert_eq!(is_visible_from_left(&forest, &3, &2), true);
+        let forest = parse_forest(&read_file(&fname));
+        let visible = scan_lines(&forest, &Direction::Left).0;
+        assert!(!visible.get(&2, &2));
+        assert!(visible.get(&3, &2));
     }
 
     #[test]
     fn test_is_visible_from_up() {
         let fname = String::from("data/test_input");
-        let forest = parse_file_to_2d_array(&read_file(&fname));
-        assert_eq!(is_visible_from_up(&forest, &1, &3), false);
-        assert_eq!(is_visible_from_up(&forest, &1, &2), true);
+        let forest = parse_forest(&read_file(&fname));
+        let visible = scan_lines(&forest, &Direction::Up).0;
+        assert!(!visible.get(&1, &3));
+        assert!(visible.get(&1, &2));
     }
 
     #[test]
     fn test_is_visible_from_down() {
         let fname = String::from("data/test_input");
-        let forest = parse_file_to_2d_array(&read_file(&fname));
-        assert_eq!(is_visible_from_down(&forest, &2, &2), false);
-        assert_eq!(is_visible_from_down(&forest, &3, &2), true);
+        let forest = parse_forest(&read_file(&fname));
+        let visible = scan_lines(&forest, &Direction::Down).0;
+        assert!(!visible.get(&2, &2));
+        assert!(visible.get(&3, &2));
     }
 
     #[test]
     fn test_is_tree_visible() {
         let fname = String::from("data/test_input");
-        let forest = parse_file_to_2d_array(&read_file(&fname));
-        assert_eq!(is_tree_visible(&forest, &1, &1), true);
+        let forest = parse_forest(&read_file(&fname));
+        assert!(get_visibility_map(&analyze_forest(&forest)).get(&1, &1));
     }
 
     #[test]
-    fn test_part1() {
+    fn test_viewing_distances() {
         let fname = String::from("data/test_input");
-        let result = solve_part1(&fname);
+        let forest = parse_forest(&read_file(&fname));
+        let expected = [
+            (Direction::Up, 1),
+            (Direction::Left, 1),
+            (Direction::Down, 2),
+            (Direction::Right, 2),
+        ];
+        for (direction, distance) in expected {
+            assert_eq!(*scan_lines(&forest, &direction).1.get(&1, &2), distance);
+        }
+        let scores = get_scenic_score_map(&analyze_forest(&forest));
+        assert_eq!(*scores.get(&1, &2), 4);
+        assert_eq!(*scores.get(&3, &2), 8);
+        assert_eq!(*scores.get(&0, &0), 0);
+    }
+
+    #[test]
+    fn test_analyze_forest() {
+        let forest = parse_forest(&read_file(&String::from("data/test_input")));
+        let views = analyze_forest(&forest);
+        let view = views.get(&1, &2);
+        assert_eq!(view.visible_from, [true, false, false, true]);
+        assert_eq!(view.distances, [1, 2, 1, 2]);
+        assert!(view.is_visible());
+        assert_eq!(view.get_scenic_score(), 4);
+        assert!(!views.get(&3, &3).is_visible());
+        let spots = get_best_spots(&views, &3);
+        assert_eq!(spots, vec![(8, [3, 2]), (6, [2, 1]), (4, [1, 2])]);
+    }
+
+    #[test]
+    fn test_heatmaps() {
+        let mut scores = Grid::new(&2, &3, 0);
+        scores.set(&0, &1, 3);
+        scores.set(&1, &2, 15);
+        assert_eq!(render_heatmap(&scores), " + \n  @\n");
+        let expected = "P3\n3 2\n255\n0 0 0 255 128 0 0 0 0 0 0 0 0 0 0 255 255 255\n";
+        assert_eq!(render_heatmap_ppm(&scores), expected);
+        // Long rows are wrapped
+        let scores = Grid::new(&1, &10, 0);
+        let ppm = render_heatmap_ppm(&scores);
+        assert!(ppm.lines().all(|line| line.len() <= 70));
+        assert_eq!(ppm.lines().count(), 4);
+    }
+
+    #[test]
+    fn test_get_ray() {
+        let grid = Grid::new(&5, &5, 0);
+        assert_eq!(grid.get_ray(&[2, 2], &[0, 1]), vec![[2, 3], [2, 4]]);
+        assert_eq!(grid.get_ray(&[2, 2], &[-1, -1]), vec![[1, 1], [0, 0]]);
+        assert_eq!(
+            grid.get_ray(&[0, 0], &[1, 2]),
+            vec![[1, 1], [1, 2], [2, 3], [2, 4]]
+        );
+        assert!(grid.get_ray(&[0, 4], &[0, 1]).is_empty());
+        assert!(grid.get_ray(&[2, 2], &[0, 0]).is_empty());
+    }
+
+    #[test]
+    fn test_viewshed() {
+        let forest = parse_forest(&read_file(&String::from("data/test_input")));
+        assert_eq!(
+            get_visible_along(&forest, &[2, 2], &3, &[0, 1]),
+            vec![[2, 3]]
+        );
+        assert_eq!(
+            get_visible_along(&forest, &[2, 2], &3, &[-1, 0]),
+            vec![[1, 2]]
+        );
+        let expected = vec![[1, 3], [0, 4]];
+        assert_eq!(get_visible_along(&forest, &[2, 2], &3, &[-1, 1]), expected);
+        // Higher observers see over more trees
+        assert_eq!(get_visible_along(&forest, &[2, 2], &9, &[0, 1]).len(), 2);
+        let visible = get_viewshed(&forest, &[2, 2], &3, &OCTANTS);
+        assert_eq!(visible.len(), 9);
+        let steps = get_edge_steps(&forest, &[2, 2]);
+        assert_eq!(steps.len(), 16);
+        assert!(get_viewshed(&forest, &[2, 2], &3, &steps).is_superset(&visible));
+        let drawing = render_viewshed(
+            &forest,
+            &[0, 0],
+            &get_viewshed(&forest, &[0, 0], &0, &OCTANTS),
+        );
+        assert_eq!(drawing.lines().next(), Some("O037."));
+    }
+
+    #[test]
+    fn test_scan_line() {
+        let heights = [3, 0, 3, 7, 3];
+        let (visible, distances) = scan_line(&heights);
+        assert_eq!(visible, vec![true, false, false, true, false]);
+        assert_eq!(distances, vec![0, 1, 2, 3, 1]);
+    }
+
+    #[test]
+    fn test_part1() {
+        let views = analyze_forest(&parse_forest(&read_file(&String::from("data/test_input"))));
+        let result = solve_part1(&views);
         assert_eq!(result, 21);
     }
 
     #[test]
     fn test_part2() {
-        let fname = String::from("data/test_input");
-        let result = solve_part2(&fname);
+        let views = analyze_forest(&parse_forest(&read_file(&String::from("data/test_input"))));
+        let result = solve_part2(&views);
         assert_eq!(result, 8);
     }
 }
@@ -63,191 +167,476 @@ mod tests {
 fn read_file(fname: &String) -> String {
     // Open file
     let path = Path::new(&fname);
-    let mut file = match File::open(&path) {
+    let mut file = match File::open(path) {
         Err(why) => panic!("couldn't open {}: {}", fname, why),
         Ok(file) => file,
     };
     // Parse file
     let mut content = String::new();
-    match file.read_to_string(&mut content) {
-        Err(why) => panic!("couldn't read {}: {}", fname, why),
-        Ok(_) => (),
+    if let Err(why) = file.read_to_string(&mut content) {
+        panic!("couldn't read {}: {}", fname, why)
     };
     content
 }
 
-fn parse_file_to_2d_array(file_content: &str) -> Vec<Vec<u32>> {
-    // Parse file content into a 2D vector
-    let mut forest: Vec<Vec<u32>> = vec![];
-    for line in file_content.lines() {
-        let row: Vec<u32> = line
-            .chars()
-            .map(|c| String::from(c).parse().unwrap())
-            .collect();
-        forest.push(row);
-    }
-    forest
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Grid<T> {
+    nrows: usize,
+    ncolumns: usize,
+    values: Vec<T>, // values stored row by row
 }
 
-fn is_tree_visible(forest: &Vec<Vec<u32>>, row: &usize, column: &usize) -> bool {
-    // Check if a tree is visible from the edge along the four directions
-    if is_visible_from_up(&forest, &row, &column)
-        || is_visible_from_down(&forest, &row, &column)
-        || is_visible_from_left(&forest, &row, &column)
-        || is_visible_from_right(&forest, &row, &column)
-    {
-        return true;
+impl<T: Clone> Grid<T> {
+    fn new(nrows: &usize, ncolumns: &usize, value: T) -> Self {
+        Grid {
+            nrows: *nrows,
+            ncolumns: *ncolumns,
+            values: vec![value; nrows * ncolumns],
+        }
     }
-    false
-}
 
-fn is_visible_from_right(forest: &Vec<Vec<u32>>, row: &usize, column: &usize) -> bool {
-    // Check if a tree is visible from the right edge
-    let ncolumns = forest[*row].len();
-    for j in column + 1..ncolumns {
-        if forest[*row][*column] <= forest[*row][j] {
-            return false;
+    fn get(&self, row: &usize, column: &usize) -> &T {
+        &self.values[row * self.ncolumns + column]
+    }
+
+    fn set(&mut self, row: &usize, column: &usize, value: T) {
+        self.values[row * self.ncolumns + column] = value;
+    }
+
+    fn get_line(&self, direction: &Direction, index: &usize) -> Vec<[usize; 2]> {
+        // Get the positions of the cells in a line of the grid, starting from the edge in the
+        // given direction
+        //
+        // Lines are rows for left and right, and columns for up and down.
+        match direction {
+            Direction::Left => (0..self.ncolumns).map(|j| [*index, j]).collect(),
+            Direction::Right => (0..self.ncolumns).rev().map(|j| [*index, j]).collect(),
+            Direction::Up => (0..self.nrows).map(|i| [i, *index]).collect(),
+            Direction::Down => (0..self.nrows).rev().map(|i| [i, *index]).collect(),
+        }
+    }
+
+    fn get_ray(&self, origin: &[usize; 2], step: &[i64; 2]) -> Vec<[usize; 2]> {
+        // Get the positions of the cells crossed by a ray that starts at the origin and goes
+        // through origin + step, until it leaves the grid (the origin is not included)
+        //
+        // Cells are chosen with Bresenham's line algorithm, tracing the line towards a target
+        // far enough to be outside the grid.
+        if step[0] == 0 && step[1] == 0 {
+            return vec![];
+        }
+        let k = (self.nrows + self.ncolumns) as i64;
+        let [r0, c0] = [origin[0] as i64, origin[1] as i64];
+        let [r1, c1] = [r0 + k * step[0], c0 + k * step[1]];
+        let (d_columns, d_rows) = ((c1 - c0).abs(), -(r1 - r0).abs());
+        let (sign_row, sign_column) = (step[0].signum(), step[1].signum());
+        let (mut row, mut column) = (r0, c0);
+        let mut error = d_columns + d_rows;
+        let mut cells = vec![];
+        loop {
+            let double_error = 2 * error;
+            if double_error >= d_rows {
+                error += d_rows;
+                column += sign_column;
+            }
+            if double_error <= d_columns {
+                error += d_columns;
+                row += sign_row;
+            }
+            let inside = (0..self.nrows as i64).contains(&row)
+                && (0..self.ncolumns as i64).contains(&column);
+            if !inside {
+                return cells;
+            }
+            cells.push([row as usize, column as usize]);
+        }
+    }
+
+    fn get_n_lines(&self, direction: &Direction) -> usize {
+        match direction {
+            Direction::Left | Direction::Right => self.nrows,
+            Direction::Up | Direction::Down => self.ncolumns,
         }
     }
-    true
 }
 
-fn is_visible_from_left(forest: &Vec<Vec<u32>>, row: &usize, column: &usize) -> bool {
-    // Check if a tree is visible from the left edge
-    for j in 0..*column {
-        if forest[*row][*column] <= forest[*row][j] {
-            return false;
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Direction {
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+const DIRECTIONS: [Direction; 4] = [
+    Direction::Up,
+    Direction::Down,
+    Direction::Left,
+    Direction::Right,
+];
+
+fn parse_forest(file_content: &str) -> Grid<u8> {
+    // Parse file content into a grid with the height of every tree
+    let nrows = file_content.lines().count();
+    let ncolumns = file_content.lines().next().map_or(0, |l| l.len());
+    let mut forest = Grid::new(&nrows, &ncolumns, 0);
+    for (i, line) in file_content.lines().enumerate() {
+        for (j, c) in line.chars().enumerate() {
+            forest.set(&i, &j, c.to_digit(10).expect("Invalid tree height") as u8);
         }
     }
-    true
+    forest
 }
 
-fn is_visible_from_up(forest: &Vec<Vec<u32>>, row: &usize, column: &usize) -> bool {
-    // Check if a tree is visible from the upmost edge
-    for i in 0..*row {
-        if forest[*row][*column] <= forest[i][*column] {
-            return false;
+fn scan_line(heights: &[u8]) -> (Vec<bool>, Vec<u32>) {
+    // Check which trees in the line are visible from its start and how far back they can see
+    //
+    // A tree is visible if it's taller than every previous one. Its viewing distance is given by
+    // the closest previous tree that is at least as tall, or the start of the line. Keep a stack
+    // with the positions of the previous trees that are not blocked by a taller later tree (so
+    // their heights decrease), then every tree is pushed and popped only once.
+    let mut visible = vec![false; heights.len()];
+    let mut distances = vec![0; heights.len()];
+    let mut stack: Vec<usize> = vec![];
+    for (i, height) in heights.iter().enumerate() {
+        while let Some(last) = stack.last() {
+            if heights[*last] >= *height {
+                break;
+            }
+            stack.pop();
         }
+        match stack.last() {
+            Some(blocking) => distances[i] = (i - blocking) as u32,
+            None => {
+                visible[i] = true;
+                distances[i] = i as u32;
+            }
+        }
+        stack.push(i);
     }
-    true
+    (visible, distances)
 }
 
-fn is_visible_from_down(forest: &Vec<Vec<u32>>, row: &usize, column: &usize) -> bool {
-    // Check if a tree is visible from the downmost edge
-    let nrows = forest.len();
-    for i in row + 1..nrows {
-        if forest[*row][*column] <= forest[i][*column] {
-            return false;
+fn scan_lines(forest: &Grid<u8>, direction: &Direction) -> (Grid<bool>, Grid<u32>) {
+    // Compute visibility from the edge and viewing distances along a direction for every tree
+    let mut visible = Grid::new(&forest.nrows, &forest.ncolumns, false);
+    let mut distances = Grid::new(&forest.nrows, &forest.ncolumns, 0);
+    for index in 0..forest.get_n_lines(direction) {
+        let line = forest.get_line(direction, &index);
+        let heights: Vec<u8> = line.iter().map(|[i, j]| *forest.get(i, j)).collect();
+        let (line_visible, line_distances) = scan_line(&heights);
+        for (k, [i, j]) in line.iter().enumerate() {
+            visible.set(i, j, line_visible[k]);
+            distances.set(i, j, line_distances[k]);
         }
     }
-    true
+    (visible, distances)
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+struct TreeView {
+    visible_from: [bool; 4], // whether the tree is visible from the edge in each direction
+    distances: [u32; 4],     // viewing distance in each direction
 }
 
-fn get_scenic_score(forest: &Vec<Vec<u32>>, row: &usize, column: &usize) -> u32 {
-    // Compute the scenic score of a given tree
-    get_scenic_score_left(&forest, &row, &column)
-        * get_scenic_score_right(&forest, &row, &column)
-        * get_scenic_score_up(&forest, &row, &column)
-        * get_scenic_score_down(&forest, &row, &column)
+impl TreeView {
+    fn is_visible(&self) -> bool {
+        self.visible_from.iter().any(|visible| *visible)
+    }
+
+    fn get_scenic_score(&self) -> u64 {
+        self.distances.iter().map(|d| *d as u64).product()
+    }
 }
 
-fn get_scenic_score_right(forest: &Vec<Vec<u32>>, row: &usize, column: &usize) -> u32 {
-    // Compute the scenic score for the right direction
-    let ncolumns = forest[*row].len();
-    let mut score: u32 = 0;
-    for j in column + 1..ncolumns {
-        score += 1;
-        if forest[*row][*column] <= forest[*row][j] {
-            break;
+fn analyze_forest(forest: &Grid<u8>) -> Grid<TreeView> {
+    // Get the visibility and viewing distances in every direction (sorted as in DIRECTIONS)
+    // for every tree in the forest
+    let mut views = Grid::new(&forest.nrows, &forest.ncolumns, TreeView::default());
+    for (k, direction) in DIRECTIONS.iter().enumerate() {
+        let (visible, distances) = scan_lines(forest, direction);
+        for (i, view) in views.values.iter_mut().enumerate() {
+            view.visible_from[k] = visible.values[i];
+            view.distances[k] = distances.values[i];
         }
     }
-    score
+    views
 }
 
-fn get_scenic_score_left(forest: &Vec<Vec<u32>>, row: &usize, column: &usize) -> u32 {
-    // Compute the scenic score for the left direction
-    let mut score: u32 = 0;
-    for j in (0..*column).rev() {
-        score += 1;
-        if forest[*row][*column] <= forest[*row][j] {
-            break;
+fn get_visibility_map(views: &Grid<TreeView>) -> Grid<bool> {
+    // Check which trees are visible from any edge
+    let mut visible = Grid::new(&views.nrows, &views.ncolumns, false);
+    visible.values = views.values.iter().map(|v| v.is_visible()).collect();
+    visible
+}
+
+fn get_scenic_score_map(views: &Grid<TreeView>) -> Grid<u64> {
+    // Compute the scenic score of every tree
+    let mut scores = Grid::new(&views.nrows, &views.ncolumns, 0);
+    scores.values = views.values.iter().map(|v| v.get_scenic_score()).collect();
+    scores
+}
+
+fn get_best_spots(views: &Grid<TreeView>, n: &usize) -> Vec<(u64, [usize; 2])> {
+    // Rank the n trees with the highest scenic scores (ties sorted by position)
+    let mut spots: Vec<(u64, [usize; 2])> = views
+        .values
+        .iter()
+        .enumerate()
+        .map(|(k, view)| {
+            let position = [k / views.ncolumns, k % views.ncolumns];
+            (view.get_scenic_score(), position)
+        })
+        .collect();
+    spots.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(&b.1)));
+    spots.truncate(*n);
+    spots
+}
+
+fn get_heat_levels(scores: &Grid<u64>) -> Vec<f64> {
+    // Scale the scores between 0 and 1 using a logarithmic scale, since a few trees have much
+    // larger scores than the rest
+    let max_score = scores.values.iter().max().copied().unwrap_or(0);
+    scores
+        .values
+        .iter()
+        .map(|score| match max_score {
+            0 => 0.0,
+            _ => (1.0 + *score as f64).ln() / (1.0 + max_score as f64).ln(),
+        })
+        .collect()
+}
+
+fn render_heatmap(scores: &Grid<u64>) -> String {
+    // Draw the scenic scores with ASCII characters, from the lowest to the highest
+    let ramp: Vec<char> = " .:-=+*#%@".chars().collect();
+    let levels = get_heat_levels(scores);
+    let mut heatmap = String::new();
+    for row in levels.chunks(scores.ncolumns.max(1)) {
+        for level in row {
+            heatmap.push(ramp[(level * (ramp.len() - 1) as f64).round() as usize]);
         }
+        heatmap.push('\n');
     }
-    score
+    heatmap
 }
 
-fn get_scenic_score_up(forest: &Vec<Vec<u32>>, row: &usize, column: &usize) -> u32 {
-    // Compute the scenic score for the up direction
-    let mut score: u32 = 0;
-    for i in (0..*row).rev() {
-        score += 1;
-        if forest[*row][*column] <= forest[i][*column] {
-            break;
+fn render_heatmap_ppm(scores: &Grid<u64>) -> String {
+    // Export the scenic scores as a plain PPM image, going from black through red and yellow
+    // to white
+    let mut ppm = format!("P3\n{} {}\n255\n", scores.ncolumns, scores.nrows);
+    let mut line = String::new();
+    for level in get_heat_levels(scores) {
+        let channel = |start: f64| ((level * 3.0 - start).clamp(0.0, 1.0) * 255.0).round();
+        let pixel = format!("{} {} {}", channel(0.0), channel(1.0), channel(2.0));
+        // Lines in plain PPM files shouldn't be longer than 70 characters
+        if !line.is_empty() && line.len() + pixel.len() + 1 > 70 {
+            ppm.push_str(&line);
+            ppm.push('\n');
+            line.clear();
         }
+        if !line.is_empty() {
+            line.push(' ');
+        }
+        line.push_str(&pixel);
+    }
+    if !line.is_empty() {
+        ppm.push_str(&line);
+        ppm.push('\n');
     }
-    score
+    ppm
 }
 
-fn get_scenic_score_down(forest: &Vec<Vec<u32>>, row: &usize, column: &usize) -> u32 {
-    // Compute the scenic score for the down direction
-    let nrows = forest.len();
-    let mut score: u32 = 0;
-    for i in row + 1..nrows {
-        score += 1;
-        if forest[*row][*column] <= forest[i][*column] {
-            break;
+const OCTANTS: [[i64; 2]; 8] = [
+    [-1, 0],
+    [-1, 1],
+    [0, 1],
+    [1, 1],
+    [1, 0],
+    [1, -1],
+    [0, -1],
+    [-1, -1],
+];
+
+fn get_visible_along(
+    forest: &Grid<u8>,
+    origin: &[usize; 2],
+    height: &u32,
+    step: &[i64; 2],
+) -> Vec<[usize; 2]> {
+    // Get the trees seen by an observer whose eyes are at the given height, standing on the
+    // origin cell and looking along a ray
+    //
+    // A tree is visible if the slope from the observer's eyes to its top is steeper than the
+    // slope to the top of every tree before it on the ray.
+    let mut max_slope = f64::NEG_INFINITY;
+    let mut visible = vec![];
+    for [row, column] in forest.get_ray(origin, step) {
+        let distance = ((row as f64 - origin[0] as f64).powi(2)
+            + (column as f64 - origin[1] as f64).powi(2))
+        .sqrt();
+        let slope = (*forest.get(&row, &column) as f64 - *height as f64) / distance;
+        if slope > max_slope {
+            visible.push([row, column]);
+            max_slope = slope;
         }
     }
-    score
+    visible
+}
+
+fn get_viewshed(
+    forest: &Grid<u8>,
+    origin: &[usize; 2],
+    height: &u32,
+    steps: &[[i64; 2]],
+) -> BTreeSet<[usize; 2]> {
+    // Get every tree seen by the observer when looking along each one of the rays
+    steps
+        .iter()
+        .flat_map(|step| get_visible_along(forest, origin, height, step))
+        .collect()
 }
 
-fn solve_part1(fname: &String) -> u32 {
-    // Read data file
-    let data = read_file(&fname);
-    // Parse input file
-    let forest = parse_file_to_2d_array(&data);
-    // Initialize number of visible trees. Count the tress that lie in the edge
-    let nrows = forest.len();
-    let ncolumns = forest[0].len();
-    let mut n_visible_trees = 2 * (nrows + ncolumns) as u32 - 4;
-    for i in 1..nrows - 1 {
-        for j in 1..ncolumns - 1 {
-            if is_tree_visible(&forest, &i, &j) {
-                n_visible_trees += 1;
-            };
+fn get_edge_steps(forest: &Grid<u8>, origin: &[usize; 2]) -> Vec<[i64; 2]> {
+    // Get the steps of the rays that go from the origin to every cell on the edges of the grid
+    let (nrows, ncolumns) = (forest.nrows as i64, forest.ncolumns as i64);
+    let mut edges: BTreeSet<[i64; 2]> = BTreeSet::new();
+    for column in 0..ncolumns {
+        edges.insert([0, column]);
+        edges.insert([nrows - 1, column]);
+    }
+    for row in 0..nrows {
+        edges.insert([row, 0]);
+        edges.insert([row, ncolumns - 1]);
+    }
+    edges
+        .iter()
+        .map(|[row, column]| [row - origin[0] as i64, column - origin[1] as i64])
+        .filter(|step| *step != [0, 0])
+        .collect()
+}
+
+fn render_viewshed(
+    forest: &Grid<u8>,
+    origin: &[usize; 2],
+    visible: &BTreeSet<[usize; 2]>,
+) -> String {
+    // Draw the forest showing the observer as O, the visible trees with their heights and the
+    // hidden ones as dots
+    let mut drawing = String::new();
+    for row in 0..forest.nrows {
+        for column in 0..forest.ncolumns {
+            if [row, column] == *origin {
+                drawing.push('O');
+            } else if visible.contains(&[row, column]) {
+                drawing.push_str(&forest.get(&row, &column).to_string());
+            } else {
+                drawing.push('.');
+            }
         }
+        drawing.push('\n');
     }
-    n_visible_trees
+    drawing
+}
+
+fn solve_part1(views: &Grid<TreeView>) -> u32 {
+    // Count the visible trees
+    get_visibility_map(views)
+        .values
+        .iter()
+        .filter(|visible| **visible)
+        .count() as u32
 }
 
-fn solve_part2(fname: &String) -> u32 {
-    // Read data file
-    let data = read_file(&fname);
-    // Parse input file
-    let forest = parse_file_to_2d_array(&data);
+fn solve_part2(views: &Grid<TreeView>) -> u64 {
     // Compute the highest scenic score
-    let nrows = forest.len();
-    let ncolumns = forest[0].len();
-    let mut highest_score: u32 = 0;
-    for i in 1..nrows - 1 {
-        for j in 1..ncolumns - 1 {
-            let score = get_scenic_score(&forest, &i, &j);
-            if highest_score < score {
-                highest_score = score;
-            };
-        }
+    get_scenic_score_map(views)
+        .values
+        .into_iter()
+        .max()
+        .unwrap_or(0)
+}
+
+fn print_tree_view(views: &Grid<TreeView>, row: &usize, column: &usize) {
+    // Print the visibility and viewing distances of a tree in every direction
+    let view = views.get(row, column);
+    for (k, direction) in DIRECTIONS.iter().enumerate() {
+        let visibility = if view.visible_from[k] {
+            "visible"
+        } else {
+            "hidden"
+        };
+        println!(
+            "{:?}: {} from the edge, sees {} trees",
+            direction, visibility, view.distances[k]
+        );
     }
-    highest_score
+    println!("Visible: {}", view.is_visible());
+    println!("Scenic score: {}", view.get_scenic_score());
 }
 
 fn main() {
-    let fname = String::from("data/input");
+    // analyze the forest if asked to:
+    //   cargo run -- top <n> [file]
+    //   cargo run -- tree <row> <column> [file]
+    //   cargo run -- heatmap [file]
+    //   cargo run -- ppm [file]
+    //   cargo run -- sight <row> <column> <height> [file]
+    //   cargo run -- viewshed <row> <column> <height> [file]
+    let args: Vec<String> = env::args().collect();
+    let get_fname = |i: usize| args.get(i).cloned().unwrap_or(String::from("data/input"));
+    let parse_arg = |i: usize| -> usize {
+        args.get(i)
+            .and_then(|arg| arg.parse().ok())
+            .expect("Missing or invalid numeric argument")
+    };
+    let command = args.get(1).map(|arg| arg.as_str());
+    if let Some("sight" | "viewshed") = command {
+        // look along the eight directions or towards every cell on the edges of the forest
+        let forest = parse_forest(&read_file(&get_fname(5)));
+        let origin = [parse_arg(2), parse_arg(3)];
+        let steps = match command {
+            Some("sight") => OCTANTS.to_vec(),
+            _ => get_edge_steps(&forest, &origin),
+        };
+        let visible = get_viewshed(&forest, &origin, &(parse_arg(4) as u32), &steps);
+        print!("{}", render_viewshed(&forest, &origin, &visible));
+        println!("Visible trees: {}", visible.len());
+        return;
+    }
+    if let Some("top" | "tree" | "heatmap" | "ppm") = command {
+        let fname = match command {
+            Some("top") => get_fname(3),
+            Some("tree") => get_fname(4),
+            _ => get_fname(2),
+        };
+        let forest = parse_forest(&read_file(&fname));
+        let views = analyze_forest(&forest);
+        let scores = get_scenic_score_map(&views);
+        match command {
+            Some("top") => {
+                for (score, [row, column]) in get_best_spots(&views, &parse_arg(2)) {
+                    println!("{} {} {}", row, column, score);
+                }
+            }
+            Some("tree") => print_tree_view(&views, &parse_arg(2), &parse_arg(3)),
+            Some("heatmap") => print!("{}", render_heatmap(&scores)),
+            _ => print!("{}", render_heatmap_ppm(&scores)),
+        }
+        return;
+    }
+
+    // otherwise read the forest from the file passed as argument, if any
+    let fname = get_fname(1);
+
+    // scan the forest once in every direction and share it between both parts
+    let views = analyze_forest(&parse_forest(&read_file(&fname)));
 
     // part 1
-    let result = solve_part1(&fname);
+    let result = solve_part1(&views);
     println!("Solution to part 1: {}", result);
 
     // // part 2
-    let result = solve_part2(&fname);
+    let result = solve_part2(&views);
     println!("Solution to part 2: {}", result);
 }
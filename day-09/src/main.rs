@@ -1,11 +1,142 @@
+use std::collections::HashSet;
+use std::env;
+use std::fmt;
+use std::fmt::Display;
+use std::fmt::Formatter;
 use std::fs::File;
 use std::io::Read;
+use std::io::Write;
 use std::path::Path;
 
 #[cfg(test)]
+#[allow(clippy::items_after_test_module)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_parse_moves() {
+        let moves = parse_moves("R 4\nUL 12\n").unwrap();
+        let expected = vec![
+            Move {
+                delta: [1, 0],
+                steps: 4,
+            },
+            Move {
+                delta: [-1, 1],
+                steps: 12,
+            },
+        ];
+        assert_eq!(moves, expected);
+        let moves: Vec<Move<3>> = parse_moves("BD 2\nR 1").unwrap();
+        assert_eq!(moves[0].delta, [0, -1, -1]);
+        assert_eq!(moves[1].delta, [1, 0, 0]);
+        assert!(parse_moves::<2>("F 1").is_err());
+        assert!(parse_moves::<2>("RL 1").is_err());
+        assert!(parse_moves::<2>("UU 1").is_err());
+        assert!(parse_moves::<2>("R x").is_err());
+    }
+
+    #[test]
+    fn test_follow_rules() {
+        let mut tail = [0, 0];
+        King.follow(&[1, 1], &mut tail);
+        assert_eq!(tail, [0, 0]);
+        King.follow(&[2, 1], &mut tail);
+        assert_eq!(tail, [1, 1]);
+        let mut tail = [0, 0];
+        Rook.follow(&[2, 1], &mut tail);
+        assert_eq!(tail, [1, 0]);
+        Rook.follow(&[2, 2], &mut tail);
+        assert_eq!(tail, [1, 1]);
+        let elastic = Elastic { slack: 2 };
+        let mut tail = [0, 0];
+        elastic.follow(&[2, -2], &mut tail);
+        assert_eq!(tail, [0, 0]);
+        elastic.follow(&[3, 0], &mut tail);
+        assert_eq!(tail, [1, 0]);
+        // Knots in 3D follow any of their 26 neighbours
+        let mut tail = [0, 0, 0];
+        King.follow(&[1, -1, 1], &mut tail);
+        assert_eq!(tail, [0, 0, 0]);
+        King.follow(&[2, -1, 1], &mut tail);
+        assert_eq!(tail, [1, -1, 1]);
+        assert!(get_rule::<2>("elastic:3").is_ok());
+        assert!(get_rule::<2>("elastic:0").is_err());
+        assert!(get_rule::<2>("queen").is_err());
+    }
+
+    #[test]
+    fn test_simulate() {
+        let moves = read_moves(&String::from("data/test_input"));
+        let rope = simulate(&moves, &10);
+        assert_eq!(rope.knots[0], [2, 2]);
+        assert_eq!(rope.get_tail(), &[0, 0]);
+        assert_eq!(rope.visited[1].len(), 13);
+        assert_eq!(rope.visited[9].len(), 1);
+        // A single knot only tracks the head
+        let rope = simulate(&moves, &1);
+        assert_eq!(rope.get_tail(), &[2, 2]);
+        assert_eq!(rope.get_tail_visited(), &rope.visited[0]);
+    }
+
+    #[test]
+    fn test_simulate_variants() {
+        // Diagonal moves drag the tail diagonally behind the head
+        let moves = parse_moves("UR 3").unwrap();
+        let rope = simulate(&moves, &2);
+        assert_eq!(rope.get_tail(), &[2, 2]);
+        // Rook knots lag behind along a single axis at a time
+        let rope = simulate_with_rule(&moves, &2, Box::new(Rook));
+        assert_eq!(rope.get_tail(), &[1, 1]);
+        // Elastic knots let the head go further away before following it
+        let moves = parse_moves("R 5").unwrap();
+        let rope = simulate_with_rule(&moves, &3, Box::new(Elastic { slack: 2 }));
+        assert_eq!(rope.knots, vec![[5, 0], [3, 0], [1, 0]]);
+        // Ropes in 3D
+        let moves = parse_moves("F 4\nUR 2").unwrap();
+        let rope = simulate(&moves, &3);
+        assert_eq!(rope.knots, vec![[2, 2, 4], [1, 1, 4], [1, 1, 3]]);
+    }
+
+    #[test]
+    fn test_display_move() {
+        let moves: Vec<Move<3>> = parse_moves("RU 3\nD 1\nBL 2").unwrap();
+        let lines: Vec<String> = moves.iter().map(|m| m.to_string()).collect();
+        assert_eq!(lines, vec!["UR 3", "D 1", "LB 2"]);
+    }
+
+    #[test]
+    fn test_render() {
+        let moves = read_moves(&String::from("data/test_input"));
+        let rope = simulate(&moves, &2);
+        let bounds = Bounds::new(rope.visited.iter().flatten());
+        assert_eq!(bounds.min, [0, 0]);
+        assert_eq!(bounds.max, [5, 4]);
+        let expected = "..##..\n...##.\n.####.\n....#.\ns###..\n";
+        assert_eq!(render_visited(&rope.visited[1], &bounds), expected);
+        let expected = "......\n......\n......\n......\ns..TH.\n";
+        assert_eq!(render_knots(&[[4, 0], [3, 0]], &bounds), expected);
+        // Knots closer to the head are drawn on top
+        let expected = "......\n......\n......\n....H.\n4321..\n";
+        let knots = [[4, 1], [3, 0], [2, 0], [1, 0], [0, 0], [0, 0]];
+        assert_eq!(render_knots(&knots, &bounds), expected);
+    }
+
+    #[test]
+    fn test_animate() {
+        let moves = parse_moves("R 2\nU 1").unwrap();
+        let frames = animate(&moves, &2, Box::new(King), &Granularity::Instruction);
+        let expected = vec![
+            "== Initial State ==\n\n...\nH..\n",
+            "== R 2 ==\n\n...\nsTH\n",
+            "== U 1 ==\n\n..H\nsT.\n",
+        ];
+        assert_eq!(frames, expected);
+        let frames = animate(&moves, &2, Box::new(King), &Granularity::Step);
+        assert_eq!(frames.len(), 4);
+        assert_eq!(frames[1], "== R 2 (step 1 of 2) ==\n\n...\nTH.\n");
+    }
+
     #[test]
     fn test_part1() {
         let fname = String::from("data/test_input");
@@ -31,128 +162,429 @@ mod tests {
 fn read_file(fname: &String) -> String {
     // Open file
     let path = Path::new(&fname);
-    let mut file = match File::open(&path) {
+    let mut file = match File::open(path) {
         Err(why) => panic!("couldn't open {}: {}", fname, why),
         Ok(file) => file,
     };
     // Parse file
     let mut content = String::new();
-    match file.read_to_string(&mut content) {
-        Err(why) => panic!("couldn't read {}: {}", fname, why),
-        Ok(_) => (),
+    if let Err(why) = file.read_to_string(&mut content) {
+        panic!("couldn't read {}: {}", fname, why)
     };
     content
 }
 
-fn update_tail_position(head: &[i32; 2], tail: &mut [i32; 2]) -> () {
-    // Update the position of the tail based on the position of the head
-    if (head[0] - tail[0]).abs() >= 2 && head[1] - tail[1] == 0 {
-        // Move it horizontally if needed
-        let mut movement = 1;
-        if head[0] - tail[0] < 0 {
-            movement *= -1
+trait FollowRule<const D: usize> {
+    // Update the position of a knot based on the position of the knot ahead of it
+    fn follow(&self, head: &[i32; D], tail: &mut [i32; D]);
+}
+
+fn get_max_distance<const D: usize>(head: &[i32; D], tail: &[i32; D]) -> i32 {
+    // Get the largest distance between two knots along any axis
+    head.iter()
+        .zip(tail.iter())
+        .map(|(h, t)| (h - t).abs())
+        .max()
+        .unwrap_or(0)
+}
+
+struct King;
+
+impl<const D: usize> FollowRule<D> for King {
+    fn follow(&self, head: &[i32; D], tail: &mut [i32; D]) {
+        // Move one step towards the head along every axis if they are no longer touching
+        // (including diagonally)
+        if get_max_distance(head, tail) >= 2 {
+            for i in 0..D {
+                tail[i] += (head[i] - tail[i]).signum();
+            }
         }
-        tail[0] += movement;
-    } else if (head[1] - tail[1]).abs() >= 2 && head[0] - tail[0] == 0 {
-        // Move it vertically if needed
-        let mut movement = 1;
-        if head[1] - tail[1] < 0 {
-            movement *= -1
+    }
+}
+
+struct Rook;
+
+impl<const D: usize> FollowRule<D> for Rook {
+    fn follow(&self, head: &[i32; D], tail: &mut [i32; D]) {
+        // Move one step towards the head along a single axis if they are no longer touching,
+        // choosing the axis along which they are farthest apart (the first one on ties)
+        if get_max_distance(head, tail) < 2 {
+            return;
         }
-        tail[1] += movement;
-    } else if (head[0] - tail[0]).abs() >= 2 || (head[1] - tail[1]).abs() >= 2 {
-        // Move it diagonally if needed
-        let mut movement_x = 1;
-        let mut movement_y = 1;
-        if head[0] - tail[0] < 0 {
-            movement_x *= -1
+        let mut axis = 0;
+        for i in 1..D {
+            if (head[i] - tail[i]).abs() > (head[axis] - tail[axis]).abs() {
+                axis = i;
+            }
         }
-        if head[1] - tail[1] < 0 {
-            movement_y *= -1
+        tail[axis] += (head[axis] - tail[axis]).signum();
+    }
+}
+
+struct Elastic {
+    slack: i32, // largest distance along any axis before the knot starts following
+}
+
+impl<const D: usize> FollowRule<D> for Elastic {
+    fn follow(&self, head: &[i32; D], tail: &mut [i32; D]) {
+        // Move like a king, but only once the head is farther away than the slack
+        if get_max_distance(head, tail) > self.slack {
+            for i in 0..D {
+                tail[i] += (head[i] - tail[i]).signum();
+            }
         }
-        tail[0] += movement_x;
-        tail[1] += movement_y;
     }
 }
 
-fn solve_part1(fname: &String) -> u32 {
-    // Read data file
-    let data = read_file(&fname);
-    // Define starting positions for the head and the tail
-    let mut head: [i32; 2] = [0, 0];
-    let mut tail: [i32; 2] = [0, 0];
-    // Define a vector with the positions that the tail visited
-    // (initialize it with the initial position of tail)
-    let mut visited: Vec<[i32; 2]> = vec![tail.clone()];
-    // Start reading the movement instructions
-    for line in data.lines() {
-        let movements: usize = line.split_whitespace().last().unwrap().parse().unwrap();
-        let direction = line.split_whitespace().nth(0).unwrap();
-        for _ in 0..movements {
-            // Update the position of the head
-            match direction {
-                "R" => head[0] += 1,
-                "L" => head[0] -= 1,
-                "U" => head[1] += 1,
-                "D" => head[1] -= 1,
-                _ => panic!("Invalid movement direction '{}'", direction),
+fn get_rule<const D: usize>(name: &str) -> Result<Box<dyn FollowRule<D>>, String> {
+    // Get a follow rule by its name: king, rook or elastic:<slack>
+    match name.split_once(':') {
+        None if name == "king" => Ok(Box::new(King)),
+        None if name == "rook" => Ok(Box::new(Rook)),
+        Some(("elastic", slack)) => match slack.parse() {
+            Ok(slack) if slack >= 1 => Ok(Box::new(Elastic { slack })),
+            _ => Err(format!("Invalid slack '{}'", slack)),
+        },
+        _ => Err(format!("Unknown follow rule '{}'", name)),
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Move<const D: usize> {
+    delta: [i32; D], // displacement of the head on every step
+    steps: usize,
+}
+
+impl<const D: usize> Display for Move<D> {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        // Write the direction with one letter per axis, vertical first (e.g. UR 3)
+        let letters = [(1, 'U', 'D'), (0, 'R', 'L'), (2, 'F', 'B')];
+        for (axis, positive, negative) in letters {
+            match self.delta.get(axis) {
+                Some(1) => write!(f, "{}", positive)?,
+                Some(-1) => write!(f, "{}", negative)?,
+                _ => (),
             }
-            // Update position of the tail
-            update_tail_position(&head, &mut tail);
-            // Add the new position of the tail to visited if it hasn't been visited
-            if !visited.contains(&tail) {
-                visited.push(tail.clone())
+        }
+        write!(f, " {}", self.steps)
+    }
+}
+
+fn parse_direction<const D: usize>(direction: &str) -> Result<[i32; D], String> {
+    // Parse a direction made out of one letter per axis: R or L along x, U or D along y and F or
+    // B along z (e.g. UR moves the head diagonally)
+    let mut delta = [0; D];
+    for letter in direction.chars() {
+        let (axis, sign) = match letter {
+            'R' => (0, 1),
+            'L' => (0, -1),
+            'U' => (1, 1),
+            'D' => (1, -1),
+            'F' => (2, 1),
+            'B' => (2, -1),
+            _ => return Err(format!("Invalid movement direction '{}'", direction)),
+        };
+        if axis >= D || delta[axis] != 0 {
+            return Err(format!("Invalid movement direction '{}'", direction));
+        }
+        delta[axis] = sign;
+    }
+    if delta.iter().all(|d| *d == 0) {
+        return Err(String::from("Empty movement direction"));
+    }
+    Ok(delta)
+}
+
+fn parse_moves<const D: usize>(content: &str) -> Result<Vec<Move<D>>, String> {
+    // Parse the movement instructions for the head of the rope
+    let mut moves = vec![];
+    for line in content.lines() {
+        let (direction, steps) = match line.split_once(' ') {
+            Some(parts) => parts,
+            None => return Err(format!("Invalid movement instruction '{}'", line)),
+        };
+        let delta = parse_direction(direction)?;
+        let steps = match steps.parse() {
+            Ok(steps) => steps,
+            Err(_) => return Err(format!("Invalid number of steps in '{}'", line)),
+        };
+        moves.push(Move { delta, steps });
+    }
+    Ok(moves)
+}
+
+struct Rope<const D: usize> {
+    knots: Vec<[i32; D]>, // positions of the knots, the first one is the head
+    visited: Vec<HashSet<[i32; D]>>, // positions visited by each one of the knots
+    rule: Box<dyn FollowRule<D>>, // how each knot follows the one ahead of it
+}
+
+impl<const D: usize> Rope<D> {
+    fn new(n_knots: &usize, rule: Box<dyn FollowRule<D>>) -> Self {
+        // Create a rope with all its knots on the origin
+        if *n_knots == 0 {
+            panic!("A rope needs at least one knot");
+        }
+        let mut visited = HashSet::new();
+        visited.insert([0; D]);
+        Rope {
+            knots: vec![[0; D]; *n_knots],
+            visited: vec![visited; *n_knots],
+            rule,
+        }
+    }
+
+    fn get_tail(&self) -> &[i32; D] {
+        self.knots.last().unwrap()
+    }
+
+    fn get_tail_visited(&self) -> &HashSet<[i32; D]> {
+        self.visited.last().unwrap()
+    }
+
+    fn step(&mut self, delta: &[i32; D]) {
+        // Move the head one step and let the rest of the knots follow it
+        for (position, d) in self.knots[0].iter_mut().zip(delta.iter()) {
+            *position += d;
+        }
+        self.visited[0].insert(self.knots[0]);
+        for i in 1..self.knots.len() {
+            let head = self.knots[i - 1];
+            let previous = self.knots[i];
+            self.rule.follow(&head, &mut self.knots[i]);
+            // The rest of the knots won't move if this one didn't
+            if self.knots[i] == previous {
+                break;
             }
+            self.visited[i].insert(self.knots[i]);
+        }
+    }
+
+    fn apply(&mut self, movement: &Move<D>) {
+        for _ in 0..movement.steps {
+            self.step(&movement.delta);
         }
     }
-    visited.len() as u32
 }
 
-fn update_knots_positions(knots: &mut Vec<[i32; 2]>) -> () {
-    // Update the positions of every knot after the head has been moved
-    for i in 1..knots.len() {
-        let head = knots[i - 1].clone();
-        update_tail_position(&head, &mut knots[i]);
+fn simulate_with_rule<const D: usize>(
+    moves: &[Move<D>],
+    n_knots: &usize,
+    rule: Box<dyn FollowRule<D>>,
+) -> Rope<D> {
+    // Apply the moves to a rope with the given number of knots that follow the given rule
+    let mut rope = Rope::new(n_knots, rule);
+    for movement in moves.iter() {
+        rope.apply(movement);
     }
+    rope
 }
 
-fn solve_part2(fname: &String) -> u32 {
-    // Read data file
-    let data = read_file(&fname);
-    // Define starting positions for the head and the multiple knots. The first element is the
-    // head, the 10th is the tail.
-    let mut knots: Vec<[i32; 2]> = vec![[0, 0]; 10];
-    // Define a vector with the positions that the tail (the last knot) visited
-    // (initialize it with the initial position of tail)
-    let mut visited: Vec<[i32; 2]> = vec![knots.last().unwrap().clone()];
-    // Start reading the movement instructions
-    for line in data.lines() {
-        let movements: usize = line.split_whitespace().last().unwrap().parse().unwrap();
-        let direction = line.split_whitespace().nth(0).unwrap();
-        for _ in 0..movements {
-            // Update the position of the head
-            match direction {
-                "R" => knots[0][0] += 1,
-                "L" => knots[0][0] -= 1,
-                "U" => knots[0][1] += 1,
-                "D" => knots[0][1] -= 1,
-                _ => panic!("Invalid movement direction '{}'", direction),
+fn simulate<const D: usize>(moves: &[Move<D>], n_knots: &usize) -> Rope<D> {
+    // Apply the moves to a rope whose knots move like a king, as in the puzzle
+    simulate_with_rule(moves, n_knots, Box::new(King))
+}
+
+fn read_moves<const D: usize>(fname: &String) -> Vec<Move<D>> {
+    match parse_moves(&read_file(fname)) {
+        Ok(moves) => moves,
+        Err(why) => panic!("couldn't parse {}: {}", fname, why),
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Bounds {
+    min: [i32; 2],
+    max: [i32; 2],
+}
+
+impl Bounds {
+    fn new<'a>(positions: impl Iterator<Item = &'a [i32; 2]>) -> Self {
+        // Get the smallest region that contains the origin and all the positions
+        let mut bounds = Bounds {
+            min: [0, 0],
+            max: [0, 0],
+        };
+        for position in positions {
+            for (i, coordinate) in position.iter().enumerate() {
+                bounds.min[i] = bounds.min[i].min(*coordinate);
+                bounds.max[i] = bounds.max[i].max(*coordinate);
             }
-            // Update position of the knots
-            update_knots_positions(&mut knots);
-            // Add the new position of the tail to visited if it hasn't been visited
-            let tail_position = knots.last().unwrap();
-            if !visited.contains(&tail_position) {
-                visited.push(tail_position.clone())
+        }
+        bounds
+    }
+
+    fn draw(&self, get_marker: impl Fn(&[i32; 2]) -> char) -> String {
+        // Draw the region row by row, with the y axis pointing up
+        let mut drawing = String::new();
+        for y in (self.min[1]..=self.max[1]).rev() {
+            for x in self.min[0]..=self.max[0] {
+                drawing.push(get_marker(&[x, y]));
+            }
+            drawing.push('\n');
+        }
+        drawing
+    }
+}
+
+fn get_knot_label(index: &usize, n_knots: &usize) -> char {
+    // Label the head as H and the rest of the knots with their index, or as T if the rope has
+    // only two knots (knots after the 9th are drawn as T too)
+    match index {
+        0 => 'H',
+        1..=9 if *n_knots != 2 => char::from_digit(*index as u32, 10).unwrap(),
+        _ => 'T',
+    }
+}
+
+fn render_knots(knots: &[[i32; 2]], bounds: &Bounds) -> String {
+    // Draw the knots on the grid (the ones closer to the head cover the rest) and the starting
+    // position as s
+    bounds.draw(
+        |position| match knots.iter().position(|knot| knot == position) {
+            Some(index) => get_knot_label(&index, &knots.len()),
+            None if *position == [0, 0] => 's',
+            None => '.',
+        },
+    )
+}
+
+fn render_visited(visited: &HashSet<[i32; 2]>, bounds: &Bounds) -> String {
+    // Draw the positions visited by a knot as #, and the starting position as s
+    bounds.draw(|position| match position {
+        [0, 0] => 's',
+        _ if visited.contains(position) => '#',
+        _ => '.',
+    })
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Granularity {
+    Step,
+    Instruction,
+}
+
+fn animate(
+    moves: &[Move<2>],
+    n_knots: &usize,
+    rule: Box<dyn FollowRule<2>>,
+    granularity: &Granularity,
+) -> Vec<String> {
+    // Draw one frame of the rope after every step or every instruction
+    //
+    // All frames share the same bounds, so they contain every position visited by the rope.
+    let mut rope = Rope::new(n_knots, rule);
+    let mut snapshots = vec![(String::from("Initial State"), rope.knots.clone())];
+    for movement in moves.iter() {
+        for step in 1..=movement.steps {
+            rope.step(&movement.delta);
+            if *granularity == Granularity::Step {
+                let title = format!("{} (step {} of {})", movement, step, movement.steps);
+                snapshots.push((title, rope.knots.clone()));
             }
         }
+        if *granularity == Granularity::Instruction {
+            snapshots.push((movement.to_string(), rope.knots.clone()));
+        }
+    }
+    let bounds = Bounds::new(rope.visited.iter().flatten());
+    snapshots
+        .iter()
+        .map(|(title, knots)| format!("== {} ==\n\n{}", title, render_knots(knots, &bounds)))
+        .collect()
+}
+
+fn write_animation(frames: &[String], fname: &String) {
+    // Write the frames to a text file, separated by blank lines
+    let mut file = match File::create(fname) {
+        Err(why) => panic!("couldn't create {}: {}", fname, why),
+        Ok(file) => file,
+    };
+    if let Err(why) = file.write_all(frames.join("\n").as_bytes()) {
+        panic!("couldn't write {}: {}", fname, why)
+    };
+}
+
+fn print_simulation<const D: usize>(fname: &String, n_knots: &usize, rule_name: &str) {
+    // Simulate a rope and print the positions visited by every knot
+    let rule = match get_rule(rule_name) {
+        Ok(rule) => rule,
+        Err(why) => panic!("{}", why),
+    };
+    let rope = simulate_with_rule(&read_moves::<D>(fname), n_knots, rule);
+    for (i, visited) in rope.visited.iter().enumerate() {
+        println!("knot {}: visited {} positions", i, visited.len());
     }
-    visited.len() as u32
+    println!("tail ends at {:?}", rope.get_tail());
+}
+
+fn solve_part1(fname: &String) -> u32 {
+    // Read data file
+    let moves: Vec<Move<2>> = read_moves(fname);
+    let rope = simulate(&moves, &2);
+    rope.get_tail_visited().len() as u32
+}
+
+fn solve_part2(fname: &String) -> u32 {
+    // Read data file
+    let moves: Vec<Move<2>> = read_moves(fname);
+    // The first knot is the head, the 10th is the tail
+    let rope = simulate(&moves, &10);
+    rope.get_tail_visited().len() as u32
 }
 
 fn main() {
     let fname = String::from("data/input");
 
+    // simulate a rope with any number of knots if asked to:
+    //   cargo run -- simulate <knots> [rule]
+    //   cargo run -- simulate3d <knots> [rule]
+    //   cargo run -- render <knots> [rule]
+    //   cargo run -- animate <knots> <step|instruction> <output> [rule]
+    let args: Vec<String> = env::args().collect();
+    let command = args.get(1).map(|arg| arg.as_str());
+    if let Some("simulate" | "simulate3d" | "render" | "animate") = command {
+        let n_knots: usize = match args.get(2).map(|arg| arg.parse()) {
+            Some(Ok(n_knots)) => n_knots,
+            _ => panic!("Missing or invalid number of knots"),
+        };
+        let rule_index = if command == Some("animate") { 5 } else { 3 };
+        let rule_name = args
+            .get(rule_index)
+            .map(|arg| arg.as_str())
+            .unwrap_or("king");
+        let rule = match get_rule(rule_name) {
+            Ok(rule) => rule,
+            Err(why) => panic!("{}", why),
+        };
+        match command {
+            Some("simulate") => print_simulation::<2>(&fname, &n_knots, rule_name),
+            Some("simulate3d") => print_simulation::<3>(&fname, &n_knots, rule_name),
+            Some("render") => {
+                let rope = simulate_with_rule(&read_moves(&fname), &n_knots, rule);
+                let bounds = Bounds::new(rope.visited.iter().flatten());
+                for (i, visited) in rope.visited.iter().enumerate() {
+                    let label = get_knot_label(&i, &n_knots);
+                    println!("== {} ==\n\n{}", label, render_visited(visited, &bounds));
+                }
+            }
+            _ => {
+                let granularity = match args.get(3).map(|arg| arg.as_str()) {
+                    Some("step") => Granularity::Step,
+                    Some("instruction") => Granularity::Instruction,
+                    _ => panic!("Granularity must be either step or instruction"),
+                };
+                let output = match args.get(4) {
+                    Some(output) => output,
+                    None => panic!("Missing output file"),
+                };
+                let frames = animate(&read_moves(&fname), &n_knots, rule, &granularity);
+                write_animation(&frames, output);
+            }
+        }
+        return;
+    }
+
     // part 1
     let result = solve_part1(&fname);
     println!("Solution to part 1: {}", result);
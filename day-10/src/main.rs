@@ -1,133 +1,1069 @@
+use std::collections::BTreeMap;
+use std::env;
+use std::fmt;
+use std::fmt::Display;
+use std::fmt::Formatter;
 use std::fs::File;
+use std::io;
+use std::io::BufRead;
 use std::io::Read;
+use std::io::Write;
 use std::path::Path;
 
 #[cfg(test)]
+#[allow(clippy::items_after_test_module)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_instruction_set_order() {
+        for (index, spec) in INSTRUCTION_SET.iter().enumerate() {
+            assert_eq!(spec.opcode as usize, index);
+        }
+    }
+
+    #[test]
+    fn test_parse_program() {
+        let program = parse_program("noop\naddx -5\n").unwrap();
+        assert_eq!(program[0].opcode, Opcode::Noop);
+        assert_eq!(program[1].opcode, Opcode::Addx);
+        assert_eq!(program[1].args, vec![-5]);
+        assert!(parse_program("addx").is_err());
+        assert!(parse_program("noop 3").is_err());
+        assert!(parse_program("addx x").is_err());
+        assert!(parse_program("mulx 3").is_err());
+    }
+
+    #[test]
+    fn test_cpu_cycles() {
+        let program = parse_program("noop\naddx 3\naddx -5").unwrap();
+        let mut cpu = Cpu::new(program);
+        let xs: Vec<i64> = cpu.by_ref().map(|cycle| cycle.x).collect();
+        assert_eq!(xs, vec![1, 1, 1, 4, 4]);
+        assert_eq!(cpu.registers.x, -1);
+        assert_eq!(cpu.cycle, 5);
+        assert!(cpu.is_halted());
+        assert_eq!(cpu.tick(), None);
+    }
+
+    #[test]
+    fn test_observers() {
+        let mut probe = SignalProbe::default();
+        let mut crt = Crt::new(&40, &6, &3);
+        let program = read_program(&String::from("data/test_input"));
+        Cpu::new(program).run(&mut [&mut probe, &mut crt]);
+        assert_eq!(probe.strength, 13140);
+        let first_row: String = crt.pixels[..40].iter().collect();
+        assert_eq!(first_row, "##..##..##..##..##..##..##..##..##..##..");
+    }
+
+    #[test]
+    fn test_assemble() {
+        let source = "; draw something
+start:  noop
+        setx 5   # x = 5
+loop: end: wait 2
+        addx -1
+";
+        let assembly = assemble(source).unwrap();
+        let program: Vec<String> = assembly.program.iter().map(|i| i.to_string()).collect();
+        assert_eq!(program, vec!["noop", "addx 4", "noop", "noop", "addx -1"]);
+        assert_eq!(assembly.labels["start"], 0);
+        assert_eq!(assembly.labels["loop"], 2);
+        assert_eq!(assembly.labels["end"], 2);
+        assert!(assemble("a: noop\na: noop").is_err());
+        assert!(assemble("wait -1").is_err());
+        assert_eq!(assemble("wait 65536").unwrap().program.len(), 65536);
+        let error = assemble("noop\nwait 1000000000000").unwrap_err();
+        assert_eq!(error, "line 2: can't wait more than 65536 cycles");
+        assert!(assemble("setx").is_err());
+        let error = assemble("noop\njump 3").unwrap_err();
+        assert_eq!(error, "line 2: Unknown instruction 'jump'");
+    }
+
+    #[test]
+    fn test_disassemble() {
+        let assembly = assemble("noop\nmid: addx 3").unwrap();
+        let expected = "     0      1  noop         ; x = 1
+mid:
+     1      2  addx 3       ; x = 4
+";
+        assert_eq!(disassemble(&assembly.program, &assembly.labels), expected);
+    }
+
+    #[test]
+    fn test_trace() {
+        let program = parse_program("noop\naddx 3").unwrap();
+        let expected = "cycle    1: x =    1  noop (1/1)
+cycle    2: x =    1  addx 3 (1/2)
+cycle    3: x =    1  addx 3 (2/2)
+";
+        assert_eq!(trace(&mut Cpu::new(program)), expected);
+    }
+
+    #[test]
+    fn test_debugger() {
+        let assembly = assemble("noop\naddx 3\nlast: addx -2\nnoop").unwrap();
+        let mut debugger = Debugger::new(assembly);
+        let output = debugger.execute("step").unwrap();
+        assert_eq!(output, "cycle 1, x = 1, next: addx 3 (1/2)\n");
+        debugger.execute("break x 4").unwrap();
+        debugger.execute("watch").unwrap();
+        let output = debugger.execute("continue").unwrap();
+        let expected = "x changed from 1 to 4 after cycle 3
+hit breakpoint on x 4
+cycle 3, x = 4, next: addx -2 (1/2)
+";
+        assert_eq!(output, expected);
+        debugger.execute("delete").unwrap();
+        debugger.execute("break cycle 5").unwrap();
+        let output = debugger.execute("c").unwrap();
+        assert!(output.ends_with("hit breakpoint on cycle 5\ncycle 5, x = 2, next: noop (1/1)\n"));
+        let output = debugger.execute("s 10").unwrap();
+        assert_eq!(output, "cycle 6, x = 2, halted\n");
+        assert!(debugger.execute("break nowhere").is_err());
+        assert!(debugger.execute("jump").is_err());
+        // Break on labels through the REPL
+        let assembly = assemble("noop\naddx 3\nlast: addx -2\nnoop").unwrap();
+        let mut debugger = Debugger::new(assembly);
+        let mut output = vec![];
+        let input = "break last\ncontinue\nquit\nregisters\n".as_bytes();
+        debugger.repl(input, &mut output).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.contains("hit breakpoint on instruction 2\ncycle 3, x = 4"));
+        assert_eq!(debugger.cpu.cycle, 3);
+        // Labels on the first instruction stop before running it, and resuming runs it
+        let assembly = assemble("start: noop\naddx 3\nnoop").unwrap();
+        let mut debugger = Debugger::new(assembly);
+        debugger.execute("break start").unwrap();
+        let output = debugger.execute("continue").unwrap();
+        let expected = "hit breakpoint on instruction 0\ncycle 0, x = 1, next: noop (1/1)\n";
+        assert_eq!(output, expected);
+        let output = debugger.execute("continue").unwrap();
+        assert_eq!(output, "program finished\ncycle 4, x = 4, halted\n");
+    }
+
     #[test]
     fn test_part1() {
         let fname = String::from("data/test_input");
         let result = solve_part1(&fname);
         assert_eq!(result, 13140);
     }
+
+    #[test]
+    fn test_recognize() {
+        let pixels: Vec<char> = [
+            ".##..#..#..",
+            "#..#.#..#..",
+            "#..#.####..",
+            "####.#..#..",
+            "#..#.#..#..",
+            "#..#.#..#..",
+        ]
+        .concat()
+        .chars()
+        .collect();
+        assert_eq!(recognize(&pixels, &11), Ok(String::from("AH ")));
+        assert!(recognize(&pixels, &10).is_err());
+        let mut pixels = pixels;
+        pixels[0] = '#';
+        let expected = "couldn't read '?H '
+unknown letter 1:
+###.
+#..#
+#..#
+####
+#..#
+#..#
+";
+        assert_eq!(recognize(&pixels, &11), Err(String::from(expected)));
+        let pixels: Vec<char> = [
+            ".###.#..#",
+            "..#..#..#",
+            "..#..####",
+            "..#..#..#",
+            "..#..#..#",
+            ".###.#..#",
+        ]
+        .concat()
+        .chars()
+        .collect();
+        assert_eq!(recognize(&pixels, &9), Ok(String::from("IH")));
+    }
+
+    #[test]
+    fn test_crt_geometry() {
+        let crt = Crt::new(&10, &2, &4);
+        assert!(!crt.covers(&5, &3));
+        assert!(crt.covers(&5, &4));
+        assert!(crt.covers(&5, &7));
+        assert!(!crt.covers(&5, &8));
+        let mut crt = Crt::new(&3, &2, &1);
+        let program = parse_program("addx 1\naddx -2\nnoop").unwrap();
+        Cpu::new(program).run(&mut [&mut crt]);
+        assert_eq!(crt.pixels.iter().collect::<String>(), ".##...");
+    }
+
+    #[test]
+    fn test_synthesize() {
+        // Draw the same letters as the test program
+        let original = run_crt(&String::from("data/test_input_2"));
+        let program = synthesize(&original, &original.pixels).unwrap();
+        let mut crt = Crt::new(&40, &6, &3);
+        Cpu::new(program).run(&mut [&mut crt]);
+        assert_eq!(crt.pixels, original.pixels);
+        // Draw on other screens
+        let (target, width) = parse_bitmap(".#..#\n..#..\n#...#\n").unwrap();
+        let mut crt = Crt::new(&width, &3, &1);
+        let program = synthesize(&crt, &target).unwrap();
+        Cpu::new(program).run(&mut [&mut crt]);
+        assert_eq!(crt.pixels, target);
+        // Pixels can't change faster than X does
+        let crt = Crt::new(&5, &1, &1);
+        let target: Vec<char> = ".....".chars().collect();
+        let error = synthesize(&crt, &target).unwrap_err();
+        assert_eq!(error, "No program can draw the pixel at row 0, column 1");
+        assert!(parse_bitmap("#.\n#").is_err());
+    }
+
+    #[test]
+    fn test_part2() {
+        let fname = String::from("data/test_input_2");
+        let result = solve_part2(&fname);
+        assert_eq!(result, "BLOCKERS");
+    }
 }
 
 fn read_file(fname: &String) -> String {
     // Open file
     let path = Path::new(&fname);
-    let mut file = match File::open(&path) {
+    let mut file = match File::open(path) {
         Err(why) => panic!("couldn't open {}: {}", fname, why),
         Ok(file) => file,
     };
     // Parse file
     let mut content = String::new();
-    match file.read_to_string(&mut content) {
-        Err(why) => panic!("couldn't read {}: {}", fname, why),
-        Ok(_) => (),
+    if let Err(why) = file.read_to_string(&mut content) {
+        panic!("couldn't read {}: {}", fname, why)
     };
     content
 }
 
-fn update_signal_strenght(cycle: &u64, x: &i64, signal_strength: &mut i64) {
-    if (*cycle as i64 - 20) % 40 == 0 {
-        *signal_strength += *cycle as i64 * x;
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Registers {
+    x: i64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Opcode {
+    Noop,
+    Addx,
+}
+
+#[derive(Debug)]
+struct InstructionSpec {
+    opcode: Opcode,
+    name: &'static str,
+    n_args: usize,
+    cycles: u64, // number of cycles the instruction takes to complete
+    execute: fn(&mut Registers, &[i64]), // effect of the instruction once it completes
+}
+
+// Set of instructions supported by the CPU, indexed by opcode
+const INSTRUCTION_SET: [InstructionSpec; 2] = [
+    InstructionSpec {
+        opcode: Opcode::Noop,
+        name: "noop",
+        n_args: 0,
+        cycles: 1,
+        execute: |_, _| {},
+    },
+    InstructionSpec {
+        opcode: Opcode::Addx,
+        name: "addx",
+        n_args: 1,
+        cycles: 2,
+        execute: |registers, args| registers.x += args[0],
+    },
+];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Instruction {
+    opcode: Opcode,
+    args: Vec<i64>,
+}
+
+impl Instruction {
+    fn spec(&self) -> &'static InstructionSpec {
+        &INSTRUCTION_SET[self.opcode as usize]
+    }
+
+    fn parse(line: &str) -> Result<Self, String> {
+        // Parse an instruction and its arguments
+        let mut tokens = line.split_whitespace();
+        let name = match tokens.next() {
+            Some(name) => name,
+            None => return Err(String::from("Empty instruction")),
+        };
+        let spec = match INSTRUCTION_SET.iter().find(|spec| spec.name == name) {
+            Some(spec) => spec,
+            None => return Err(format!("Unknown instruction '{}'", name)),
+        };
+        let mut args = vec![];
+        for token in tokens {
+            match token.parse() {
+                Ok(arg) => args.push(arg),
+                Err(_) => return Err(format!("Invalid argument '{}' in '{}'", token, line)),
+            }
+        }
+        if args.len() != spec.n_args {
+            return Err(format!(
+                "Instruction '{}' takes {} arguments, got {}",
+                name,
+                spec.n_args,
+                args.len()
+            ));
+        }
+        Ok(Instruction {
+            opcode: spec.opcode,
+            args,
+        })
     }
 }
 
-fn solve_part1(fname: &String) -> i64 {
-    // Read data file
-    let data = read_file(&fname);
-    // Initialize variables
-    let mut cycle: u64 = 0;
-    let mut x: i64 = 1;
-    let mut signal_strength: i64 = 0;
-    // Read instructions
-    for line in data.lines() {
-        let instruction = line.split_whitespace().nth(0).unwrap();
-        match instruction {
-            "noop" => {
-                cycle += 1;
-                update_signal_strenght(&cycle, &x, &mut signal_strength);
-            }
-            "addx" => {
-                for i in 0..2 {
-                    // End the current cycle
-                    cycle += 1;
-                    update_signal_strenght(&cycle, &x, &mut signal_strength);
-                    // Add value to X only AFTER the second cycle of addx
-                    if i == 1 {
-                        let value: i64 = line.split_whitespace().last().unwrap().parse().unwrap();
-                        x += value;
-                    }
-                }
+impl Display for Instruction {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "{}", self.spec().name)?;
+        for arg in self.args.iter() {
+            write!(f, " {}", arg)?;
+        }
+        Ok(())
+    }
+}
+
+fn parse_program(content: &str) -> Result<Vec<Instruction>, String> {
+    content.lines().map(Instruction::parse).collect()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Cycle {
+    number: u64, // number of the cycle, starting from 1
+    x: i64,      // value of the X register during the cycle
+}
+
+#[derive(Debug, Clone)]
+struct Cpu {
+    registers: Registers,
+    program: Vec<Instruction>,
+    pointer: usize, // index of the instruction being executed
+    elapsed: u64,   // cycles spent on the instruction being executed
+    cycle: u64,     // number of cycles completed
+}
+
+impl Cpu {
+    fn new(program: Vec<Instruction>) -> Self {
+        Cpu {
+            registers: Registers { x: 1 },
+            program,
+            pointer: 0,
+            elapsed: 0,
+            cycle: 0,
+        }
+    }
+
+    fn get_current(&self) -> Option<(&Instruction, u64)> {
+        // Get the instruction being executed and the cycles already spent on it
+        self.program
+            .get(self.pointer)
+            .map(|instruction| (instruction, self.elapsed))
+    }
+
+    fn is_halted(&self) -> bool {
+        self.pointer >= self.program.len()
+    }
+
+    fn tick(&mut self) -> Option<Cycle> {
+        // Run a single cycle and return the state of the CPU during it
+        //
+        // Instructions take effect at the end of their last cycle.
+        if self.is_halted() {
+            return None;
+        }
+        self.cycle += 1;
+        let during = Cycle {
+            number: self.cycle,
+            x: self.registers.x,
+        };
+        let instruction = &self.program[self.pointer];
+        self.elapsed += 1;
+        if self.elapsed == instruction.spec().cycles {
+            (instruction.spec().execute)(&mut self.registers, &instruction.args);
+            self.pointer += 1;
+            self.elapsed = 0;
+        }
+        Some(during)
+    }
+
+    fn run(&mut self, observers: &mut [&mut dyn Observer]) {
+        // Run the program until it finishes, notifying the observers on every cycle
+        for cycle in self.by_ref() {
+            for observer in observers.iter_mut() {
+                observer.observe(&cycle);
+            }
+        }
+    }
+}
+
+impl Iterator for Cpu {
+    type Item = Cycle;
+
+    fn next(&mut self) -> Option<Cycle> {
+        self.tick()
+    }
+}
+
+trait Observer {
+    fn observe(&mut self, cycle: &Cycle);
+}
+
+#[derive(Debug, Default)]
+struct SignalProbe {
+    strength: i64, // sum of the signal strengths during the 20th, 60th, 100th... cycles
+}
+
+impl Observer for SignalProbe {
+    fn observe(&mut self, cycle: &Cycle) {
+        if (cycle.number as i64 - 20) % 40 == 0 {
+            self.strength += cycle.number as i64 * cycle.x;
+        }
+    }
+}
+
+struct Crt {
+    width: usize,
+    height: usize,
+    sprite_width: usize,
+    pixels: Vec<char>, // pixels of the screen stored row by row
+}
+
+impl Crt {
+    fn new(width: &usize, height: &usize, sprite_width: &usize) -> Self {
+        Crt {
+            width: *width,
+            height: *height,
+            sprite_width: *sprite_width,
+            pixels: vec!['.'; width * height],
+        }
+    }
+
+    fn covers(&self, x: &i64, column: &usize) -> bool {
+        // Check if the sprite, centered on X, is over the given column
+        let left = x - (self.sprite_width as i64 - 1) / 2;
+        let column = *column as i64;
+        column >= left && column < left + self.sprite_width as i64
+    }
+}
+
+impl Observer for Crt {
+    fn observe(&mut self, cycle: &Cycle) {
+        // Draw the pixel of the cycle if the sprite is over it
+        let index = (cycle.number - 1) as usize;
+        if index >= self.pixels.len() {
+            return;
+        }
+        if self.covers(&cycle.x, &(index % self.width)) {
+            self.pixels[index] = '#';
+        }
+    }
+}
+
+fn parse_bitmap(content: &str) -> Result<(Vec<char>, usize), String> {
+    // Parse a bitmap made of rows of # and . and return its pixels and its width
+    let rows: Vec<&str> = content.lines().filter(|row| !row.is_empty()).collect();
+    let width = rows.first().map(|row| row.len()).unwrap_or(0);
+    if width == 0 {
+        return Err(String::from("Empty bitmap"));
+    }
+    let mut pixels = vec![];
+    for row in rows.iter() {
+        if row.len() != width || !row.chars().all(|c| c == '#' || c == '.') {
+            return Err(format!("Invalid bitmap row '{}'", row));
+        }
+        pixels.extend(row.chars());
+    }
+    Ok((pixels, width))
+}
+
+fn synthesize(crt: &Crt, target: &[char]) -> Result<Vec<Instruction>, String> {
+    // Build a program that draws the target bitmap on the CRT
+    //
+    // X only changes at the end of an addx, and stays the same during both of its cycles, so
+    // find the values of X during every cycle that draw the right pixels by keeping track of the
+    // values that X can take at the start of every instruction. Values of X far enough from the
+    // screen all draw the same pixels, so only a few of them need to be tried.
+    let n_pixels = crt.width * crt.height;
+    if target.len() != n_pixels {
+        return Err(format!(
+            "Bitmap of {} pixels doesn't fit a {}x{} screen",
+            target.len(),
+            crt.width,
+            crt.height
+        ));
+    }
+    let margin = crt.sprite_width as i64 + 1;
+    let values: Vec<i64> = (-margin..crt.width as i64 + margin).collect();
+    let offset = margin;
+    let draws_target = |cycle: usize, x: i64| {
+        cycle >= n_pixels || crt.covers(&x, &(cycle % crt.width)) == (target[cycle] == '#')
+    };
+    // Value of X during an addx that starts at each cycle and draws both of its pixels, if any.
+    // An addx can set X to any value, so one of them is enough to reach every value two cycles
+    // later. Otherwise, X can only be reached with a noop that keeps it from the previous cycle.
+    let mut addx_from: Vec<Option<i64>> = vec![None; n_pixels];
+    // Values of X that can be reached at the start of the current cycle
+    let mut reached = vec![false; values.len()];
+    reached[(1 + offset) as usize] = true;
+    let mut furthest = 0;
+    for cycle in 0..=n_pixels {
+        if cycle >= 2 && addx_from[cycle - 2].is_some() {
+            reached.fill(true);
+        }
+        if cycle == n_pixels {
+            break;
+        }
+        for x in values.iter() {
+            let index = (x + offset) as usize;
+            if !reached[index] {
+                continue;
+            }
+            if !draws_target(cycle, *x) {
+                reached[index] = false;
+                continue;
+            }
+            furthest = furthest.max(cycle + 1);
+            if addx_from[cycle].is_none() && draws_target(cycle + 1, *x) {
+                addx_from[cycle] = Some(*x);
             }
-            _ => panic!("Unknown instruction '{}'", instruction),
         }
     }
-    signal_strength
+    // Walk back from any program that draws the whole screen
+    let (mut cycle, mut x) = match reached.iter().position(|reached| *reached) {
+        Some(index) => (n_pixels, index as i64 - offset),
+        None => match addx_from[n_pixels - 1] {
+            Some(during) => (n_pixels + 1, during),
+            None => {
+                return Err(format!(
+                    "No program can draw the pixel at row {}, column {}",
+                    furthest / crt.width,
+                    furthest % crt.width
+                ))
+            }
+        },
+    };
+    let mut program = vec![];
+    while cycle > 0 {
+        let instruction = match cycle >= 2 && addx_from[cycle - 2].is_some() {
+            true => {
+                cycle -= 2;
+                let during = addx_from[cycle].unwrap();
+                let instruction = format!("addx {}", x - during);
+                x = during;
+                instruction
+            }
+            false => {
+                cycle -= 1;
+                String::from("noop")
+            }
+        };
+        program.push(Instruction::parse(&instruction)?);
+    }
+    program.reverse();
+    Ok(program)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Assembly {
+    program: Vec<Instruction>,
+    labels: BTreeMap<String, usize>, // index of the instruction that follows each label
+}
+
+// Longest wait that a single line of assembly can expand to, in cycles
+const MAX_WAIT: i64 = 1 << 16;
+
+fn strip_comment(line: &str) -> &str {
+    // Remove comments, which start with ; or #
+    match line.find([';', '#']) {
+        Some(index) => &line[..index],
+        None => line,
+    }
 }
 
-fn draw_pixel_on_crt(crt: &mut [char; 40 * 6], cycle: &u64, x: &i64) {
-    // Draw pixel at the given position based on the location of x
+fn assemble(source: &str) -> Result<Assembly, String> {
+    // Compile an assembly source into a program made of the instructions of the CPU
     //
-    // The position of the pixel based on the cycle
-    // Get the horizontal position of the current pixel
-    let horizontal_position: i64 = *cycle as i64 % 40;
-    if (x - horizontal_position).abs() <= 1 {
-        crt[*cycle as usize] = '#';
-    }
-}
-
-fn solve_part2(fname: &String) -> [char; 40 * 6] {
-    // Read data file
-    let data = read_file(&fname);
-    // Initialize variables
-    let mut cycle: u64 = 0;
-    let mut x: i64 = 1;
-    let mut crt: [char; 40 * 6] = ['.'; 40 * 6];
-    // Read instructions
-    for line in data.lines() {
-        let instruction = line.split_whitespace().nth(0).unwrap();
-        match instruction {
-            "noop" => {
-                // Start current cycle and draw pixel in crt.
-                draw_pixel_on_crt(&mut crt, &cycle, &x);
-                // End the current cycle
-                cycle += 1;
-            }
-            "addx" => {
-                for i in 0..2 {
-                    // Start current cycle and draw pixel in crt.
-                    draw_pixel_on_crt(&mut crt, &cycle, &x);
-                    // End the current cycle
-                    cycle += 1;
-                    // Add value to X only AFTER the second cycle of addx
-                    if i == 1 {
-                        let value: i64 = line.split_whitespace().last().unwrap().parse().unwrap();
-                        x += value;
+    // Besides the instructions of the CPU, the source may contain comments, labels (a name
+    // followed by a colon) and the following pseudo-instructions:
+    //   setx <value>: set the X register to the given value (compiled to a single addx)
+    //   wait <cycles>: do nothing for the given number of cycles (compiled to noops, up to
+    //     MAX_WAIT cycles)
+    let mut assembly = Assembly {
+        program: vec![],
+        labels: BTreeMap::new(),
+    };
+    // Programs can't jump, so the value of X is known at every point of the program
+    let mut registers = Registers { x: 1 };
+    for (i, line) in source.lines().enumerate() {
+        let mut line = strip_comment(line).trim();
+        while let Some((label, rest)) = line.split_once(':') {
+            let label = label.trim();
+            if label.is_empty() || !label.chars().all(|c| c.is_alphanumeric() || c == '_') {
+                return Err(format!("line {}: invalid label '{}'", i + 1, label));
+            }
+            if assembly.labels.contains_key(label) {
+                return Err(format!("line {}: duplicated label '{}'", i + 1, label));
+            }
+            assembly
+                .labels
+                .insert(String::from(label), assembly.program.len());
+            line = rest.trim();
+        }
+        if line.is_empty() {
+            continue;
+        }
+        let mut tokens = line.split_whitespace();
+        let mnemonic = tokens.next().unwrap();
+        let arg = tokens.next().map(|arg| arg.parse::<i64>());
+        let instructions = match (mnemonic, arg, tokens.next()) {
+            ("setx", Some(Ok(value)), None) => {
+                vec![Instruction::parse(&format!(
+                    "addx {}",
+                    value - registers.x
+                ))?]
+            }
+            ("wait", Some(Ok(cycles)), None) if cycles > MAX_WAIT => {
+                return Err(format!(
+                    "line {}: can't wait more than {} cycles",
+                    i + 1,
+                    MAX_WAIT
+                ))
+            }
+            ("wait", Some(Ok(cycles)), None) if cycles >= 0 => {
+                vec![Instruction::parse("noop")?; cycles as usize]
+            }
+            ("setx" | "wait", _, _) => {
+                return Err(format!("line {}: invalid arguments in '{}'", i + 1, line))
+            }
+            _ => match Instruction::parse(line) {
+                Ok(instruction) => vec![instruction],
+                Err(why) => return Err(format!("line {}: {}", i + 1, why)),
+            },
+        };
+        for instruction in instructions {
+            (instruction.spec().execute)(&mut registers, &instruction.args);
+            assembly.program.push(instruction);
+        }
+    }
+    Ok(assembly)
+}
+
+fn disassemble(program: &[Instruction], labels: &BTreeMap<String, usize>) -> String {
+    // List every instruction of the program with its index, the cycle in which it starts and the
+    // value of X once it completes
+    let mut listing = String::new();
+    let mut registers = Registers { x: 1 };
+    let mut cycle = 1;
+    for (index, instruction) in program.iter().enumerate() {
+        for (label, _) in labels.iter().filter(|(_, i)| **i == index) {
+            listing.push_str(&format!("{}:\n", label));
+        }
+        (instruction.spec().execute)(&mut registers, &instruction.args);
+        listing.push_str(&format!(
+            "{:>6} {:>6}  {:<12} ; x = {}\n",
+            index,
+            cycle,
+            instruction.to_string(),
+            registers.x
+        ));
+        cycle += instruction.spec().cycles;
+    }
+    listing
+}
+
+fn trace(cpu: &mut Cpu) -> String {
+    // Run the CPU until it halts, describing every cycle
+    let mut lines = String::new();
+    while let Some((instruction, elapsed)) = cpu.get_current() {
+        let description = format!(
+            "{} ({}/{})",
+            instruction,
+            elapsed + 1,
+            instruction.spec().cycles
+        );
+        let cycle = cpu.tick().unwrap();
+        lines.push_str(&format!(
+            "cycle {:>4}: x = {:>4}  {}\n",
+            cycle.number, cycle.x, description
+        ));
+    }
+    lines
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Breakpoint {
+    Cycle(u64),   // stop once the given cycle has run
+    X(i64),       // stop once X changes to the given value
+    Label(usize), // stop before starting the instruction with the given index
+}
+
+impl Display for Breakpoint {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Breakpoint::Cycle(cycle) => write!(f, "cycle {}", cycle),
+            Breakpoint::X(x) => write!(f, "x {}", x),
+            Breakpoint::Label(index) => write!(f, "instruction {}", index),
+        }
+    }
+}
+
+struct Debugger {
+    cpu: Cpu,
+    labels: BTreeMap<String, usize>,
+    breakpoints: Vec<Breakpoint>,
+    watch: bool,    // whether to report every change of X
+    on_label: bool, // whether execution stopped before an instruction on a label breakpoint
+}
+
+impl Debugger {
+    fn new(assembly: Assembly) -> Self {
+        Debugger {
+            cpu: Cpu::new(assembly.program),
+            labels: assembly.labels,
+            breakpoints: vec![],
+            watch: false,
+            on_label: false,
+        }
+    }
+
+    fn get_state(&self) -> String {
+        let instruction = match self.cpu.get_current() {
+            Some((instruction, elapsed)) => format!(
+                "next: {} ({}/{})",
+                instruction,
+                elapsed + 1,
+                instruction.spec().cycles
+            ),
+            None => String::from("halted"),
+        };
+        format!(
+            "cycle {}, x = {}, {}\n",
+            self.cpu.cycle, self.cpu.registers.x, instruction
+        )
+    }
+
+    fn step(&mut self, output: &mut String) -> Option<Breakpoint> {
+        // Run a single cycle and return the breakpoint that it hit, if any
+        //
+        // Labels are checked before fetching their instruction (including the first one), so the
+        // cycle isn't run if they are hit. Resuming from a label runs its instruction.
+        if self.cpu.elapsed == 0 && !self.on_label {
+            let hit = self.breakpoints.iter().find(|breakpoint| {
+                matches!(breakpoint, Breakpoint::Label(index) if self.cpu.pointer == *index)
+            });
+            if let Some(breakpoint) = hit {
+                self.on_label = true;
+                return Some(breakpoint.clone());
+            }
+        }
+        self.on_label = false;
+        let previous = self.cpu.registers.x;
+        let cycle = self.cpu.tick()?;
+        let x = self.cpu.registers.x;
+        if self.watch && x != previous {
+            output.push_str(&format!(
+                "x changed from {} to {} after cycle {}\n",
+                previous, x, cycle.number
+            ));
+        }
+        self.breakpoints
+            .iter()
+            .find(|breakpoint| match breakpoint {
+                Breakpoint::Cycle(number) => cycle.number == *number,
+                Breakpoint::X(value) => x != previous && x == *value,
+                Breakpoint::Label(_) => false,
+            })
+            .cloned()
+    }
+
+    fn parse_breakpoint(&self, args: &[&str]) -> Result<Breakpoint, String> {
+        match args {
+            ["cycle", number] => match number.parse() {
+                Ok(number) => Ok(Breakpoint::Cycle(number)),
+                Err(_) => Err(format!("Invalid cycle '{}'", number)),
+            },
+            ["x", value] => match value.parse() {
+                Ok(value) => Ok(Breakpoint::X(value)),
+                Err(_) => Err(format!("Invalid value '{}'", value)),
+            },
+            [label] => match self.labels.get(*label) {
+                Some(index) => Ok(Breakpoint::Label(*index)),
+                None => Err(format!("Unknown label '{}'", label)),
+            },
+            _ => Err(String::from(
+                "Usage: break cycle <n> | break x <value> | break <label>",
+            )),
+        }
+    }
+
+    fn execute(&mut self, command: &str) -> Result<String, String> {
+        // Execute a debugger command and return its output
+        let tokens: Vec<&str> = command.split_whitespace().collect();
+        let mut output = String::new();
+        match tokens.as_slice() {
+            ["step" | "s"] | ["step" | "s", _] => {
+                let n_cycles = match tokens.get(1).map(|n| n.parse::<u64>()) {
+                    None => 1,
+                    Some(Ok(n_cycles)) => n_cycles,
+                    Some(Err(_)) => {
+                        return Err(format!("Invalid number of cycles '{}'", tokens[1]))
                     }
+                };
+                for _ in 0..n_cycles {
+                    if self.cpu.is_halted() {
+                        break;
+                    }
+                    if let Some(breakpoint) = self.step(&mut output) {
+                        output.push_str(&format!("hit breakpoint on {}\n", breakpoint));
+                        break;
+                    }
+                }
+            }
+            ["continue" | "c"] => loop {
+                if self.cpu.is_halted() {
+                    output.push_str("program finished\n");
+                    break;
+                }
+                if let Some(breakpoint) = self.step(&mut output) {
+                    output.push_str(&format!("hit breakpoint on {}\n", breakpoint));
+                    break;
+                }
+            },
+            ["break" | "b", args @ ..] => {
+                let breakpoint = self.parse_breakpoint(args)?;
+                output.push_str(&format!("breakpoint on {}\n", breakpoint));
+                self.breakpoints.push(breakpoint);
+                return Ok(output);
+            }
+            ["delete" | "d"] => {
+                self.breakpoints.clear();
+                return Ok(String::from("deleted all breakpoints\n"));
+            }
+            ["watch" | "w"] => {
+                self.watch = !self.watch;
+                let state = if self.watch { "on" } else { "off" };
+                return Ok(format!("watching x is {}\n", state));
+            }
+            ["registers" | "r"] => (),
+            _ => return Err(format!("Unknown command '{}'", command.trim())),
+        }
+        output.push_str(&self.get_state());
+        Ok(output)
+    }
+
+    fn repl(&mut self, reader: impl BufRead, writer: &mut impl Write) -> io::Result<()> {
+        // Read commands until the input ends or the user quits
+        write!(writer, "{}(dbg) ", self.get_state())?;
+        writer.flush()?;
+        for line in reader.lines() {
+            let line = line?;
+            if let "quit" | "q" = line.trim() {
+                break;
+            }
+            if !line.trim().is_empty() {
+                match self.execute(&line) {
+                    Ok(output) => write!(writer, "{}", output)?,
+                    Err(why) => writeln!(writer, "error: {}", why)?,
                 }
             }
-            _ => panic!("Unknown instruction '{}'", instruction),
+            write!(writer, "(dbg) ")?;
+            writer.flush()?;
+        }
+        Ok(())
+    }
+}
+
+// Letters drawn by the CRT, 4 pixels wide and 6 pixels tall
+const FONT: [(char, [&str; 6]); 17] = [
+    ('A', [".##.", "#..#", "#..#", "####", "#..#", "#..#"]),
+    ('B', ["###.", "#..#", "###.", "#..#", "#..#", "###."]),
+    ('C', [".##.", "#..#", "#...", "#...", "#..#", ".##."]),
+    ('E', ["####", "#...", "###.", "#...", "#...", "####"]),
+    ('F', ["####", "#...", "###.", "#...", "#...", "#..."]),
+    ('G', [".##.", "#..#", "#...", "#.##", "#..#", ".###"]),
+    ('H', ["#..#", "#..#", "####", "#..#", "#..#", "#..#"]),
+    ('I', [".###", "..#.", "..#.", "..#.", "..#.", ".###"]),
+    ('J', ["..##", "...#", "...#", "...#", "#..#", ".##."]),
+    ('K', ["#..#", "#.#.", "##..", "#.#.", "#.#.", "#..#"]),
+    ('L', ["#...", "#...", "#...", "#...", "#...", "####"]),
+    ('O', [".##.", "#..#", "#..#", "#..#", "#..#", ".##."]),
+    ('P', ["###.", "#..#", "#..#", "###.", "#...", "#..."]),
+    ('R', ["###.", "#..#", "#..#", "###.", "#.#.", "#..#"]),
+    ('S', [".###", "#...", "#...", ".##.", "...#", "###."]),
+    ('U', ["#..#", "#..#", "#..#", "#..#", "#..#", ".##."]),
+    ('Z', ["####", "...#", "..#.", ".#..", "#...", "####"]),
+];
+
+fn recognize(pixels: &[char], width: &usize) -> Result<String, String> {
+    // Read the letters drawn on a screen that is 6 pixels tall
+    //
+    // Letters are separated by a blank column, so each one of them takes 5 columns of the
+    // screen. Blank letters are read as spaces. If some letters are not part of the font, return
+    // an error with their bitmaps.
+    if *width == 0 || pixels.len() != width * 6 {
+        return Err(format!(
+            "Screen of {} pixels isn't 6 rows of {}",
+            pixels.len(),
+            width
+        ));
+    }
+    let mut text = String::new();
+    let mut unknown = vec![];
+    for (i, start) in (0..*width).step_by(5).enumerate() {
+        let end = (start + 4).min(*width);
+        let bitmap: Vec<String> = (0..6)
+            .map(|row| {
+                pixels[row * width + start..row * width + end]
+                    .iter()
+                    .collect()
+            })
+            .collect();
+        if bitmap.iter().all(|row| !row.contains('#')) {
+            text.push(' ');
+            continue;
+        }
+        match FONT.iter().find(|(_, glyph)| *glyph == bitmap.as_slice()) {
+            Some((letter, _)) => text.push(*letter),
+            None => {
+                text.push('?');
+                unknown.push(format!(
+                    "unknown letter {}:\n{}\n",
+                    i + 1,
+                    bitmap.join("\n")
+                ));
+            }
         }
     }
+    if !unknown.is_empty() {
+        return Err(format!("couldn't read '{}'\n{}", text, unknown.join("")));
+    }
+    Ok(text)
+}
+
+fn print_screen(pixels: &[char], width: &usize) {
+    for row in pixels.chunks(*width) {
+        println!("{}", row.iter().collect::<String>());
+    }
+}
+
+fn read_program(fname: &String) -> Vec<Instruction> {
+    match parse_program(&read_file(fname)) {
+        Ok(program) => program,
+        Err(why) => panic!("couldn't parse {}: {}", fname, why),
+    }
+}
+
+fn solve_part1(fname: &String) -> i64 {
+    let mut probe = SignalProbe::default();
+    Cpu::new(read_program(fname)).run(&mut [&mut probe]);
+    probe.strength
+}
+
+fn run_crt(fname: &String) -> Crt {
+    let mut crt = Crt::new(&40, &6, &3);
+    Cpu::new(read_program(fname)).run(&mut [&mut crt]);
     crt
 }
 
+fn solve_part2(fname: &String) -> String {
+    let crt = run_crt(fname);
+    match recognize(&crt.pixels, &crt.width) {
+        Ok(text) => text,
+        Err(why) => panic!("{}", why),
+    }
+}
+
+fn read_assembly(fname: &String) -> Assembly {
+    match assemble(&read_file(fname)) {
+        Ok(assembly) => assembly,
+        Err(why) => panic!("couldn't assemble {}: {}", fname, why),
+    }
+}
+
 fn main() {
     let fname = String::from("data/input");
 
+    // run the tools for programs if asked to:
+    //   cargo run -- assemble <source>
+    //   cargo run -- disassemble [source]
+    //   cargo run -- trace [source]
+    //   cargo run -- debug [source]
+    //   cargo run -- screen [program]
+    //   cargo run -- synthesize <bitmap> [sprite width]
+    let args: Vec<String> = env::args().collect();
+    let command = args.get(1).map(|arg| arg.as_str());
+    if command == Some("screen") {
+        let crt = run_crt(args.get(2).unwrap_or(&fname));
+        print_screen(&crt.pixels, &crt.width);
+        return;
+    }
+    if command == Some("synthesize") {
+        let bitmap = match args.get(2) {
+            Some(bitmap) => bitmap,
+            None => panic!("Missing bitmap file"),
+        };
+        let (target, width) = match parse_bitmap(&read_file(bitmap)) {
+            Ok(parsed) => parsed,
+            Err(why) => panic!("couldn't parse {}: {}", bitmap, why),
+        };
+        let sprite_width = match args.get(3).map(|arg| arg.parse()) {
+            None => 3,
+            Some(Ok(sprite_width)) => sprite_width,
+            Some(Err(_)) => panic!("Invalid sprite width"),
+        };
+        let crt = Crt::new(&width, &(target.len() / width), &sprite_width);
+        match synthesize(&crt, &target) {
+            Ok(program) => program
+                .iter()
+                .for_each(|instruction| println!("{}", instruction)),
+            Err(why) => panic!("{}", why),
+        }
+        return;
+    }
+    if let Some("assemble" | "disassemble" | "trace" | "debug") = command {
+        let source = args.get(2).unwrap_or(&fname);
+        let assembly = read_assembly(source);
+        match command {
+            Some("assemble") => {
+                for instruction in assembly.program.iter() {
+                    println!("{}", instruction);
+                }
+            }
+            Some("disassemble") => print!("{}", disassemble(&assembly.program, &assembly.labels)),
+            Some("trace") => print!("{}", trace(&mut Cpu::new(assembly.program))),
+            _ => {
+                let mut debugger = Debugger::new(assembly);
+                if let Err(why) = debugger.repl(io::stdin().lock(), &mut io::stdout()) {
+                    panic!("debugger failed: {}", why)
+                }
+            }
+        }
+        return;
+    }
+
     // part 1
     let result = solve_part1(&fname);
     println!("Solution to part 1: {}", result);
 
     // // part 2
     let result = solve_part2(&fname);
-    println!("Solution to part 2:");
-    for i in 0..6 {
-        let crt: String = result[40 * i..40 * (i + 1)].iter().cloned().collect();
-        println!("{}", crt);
-    }
+    println!("Solution to part 2: {}", result);
 }
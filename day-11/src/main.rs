@@ -1,11 +1,182 @@
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::collections::VecDeque;
+use std::env;
+use std::fmt;
+use std::fmt::Display;
+use std::fmt::Formatter;
 use std::fs::File;
 use std::io::Read;
 use std::path::Path;
 
 #[cfg(test)]
+#[allow(clippy::items_after_test_module)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_parse_expr() {
+        let old = Box::new(Expr::Old);
+        let expected = Expr::Operation(old.clone(), Operator::Mul, Box::new(Expr::Literal(19)));
+        assert_eq!(Expr::parse(" old * 19"), Ok(expected));
+        let expected = Expr::Operation(Box::new(Expr::Literal(3)), Operator::Add, old.clone());
+        assert_eq!(Expr::parse("3+old"), Ok(expected));
+        let expected = Expr::Operation(old.clone(), Operator::Add, old.clone());
+        assert_eq!(Expr::parse("old + old"), Ok(expected));
+        let expr = Expr::parse("2 + old * old / 4 - 1").unwrap();
+        assert_eq!(expr.to_string(), "2 + old * old / 4 - 1");
+        assert_eq!(expr.evaluate(&6u64), Ok(10));
+        assert!(Expr::parse("old *").is_err());
+        assert!(Expr::parse("old old").is_err());
+        assert!(Expr::parse("old % 2").is_err());
+        assert!(Expr::parse("new").is_err());
+    }
+
+    #[test]
+    fn test_evaluate_checked() {
+        let expr = Expr::parse("old * old").unwrap();
+        assert_eq!(expr.evaluate(&(1u64 << 31)), Ok(1 << 62));
+        assert_eq!(expr.evaluate(&(1u64 << 32)), Err(EvalError::Overflow));
+        let expr = Expr::parse("old - 5").unwrap();
+        assert_eq!(expr.evaluate(&4u64), Err(EvalError::BelowZero));
+        let expr = Expr::parse("5 / old").unwrap();
+        assert_eq!(expr.evaluate(&0u64), Err(EvalError::DivisionByZero));
+        let zero = BigUint::from_u64(0);
+        assert_eq!(expr.evaluate(&zero), Err(EvalError::DivisionByZero));
+        // Reduced worry levels wrap around the modulus
+        let old = ReducedWorry {
+            value: 4,
+            modulus: 10,
+        };
+        let expr = Expr::parse("old - 5 + old * 3").unwrap();
+        assert_eq!(expr.evaluate(&old), Ok(old.with_value(1)));
+    }
+
+    #[test]
+    fn test_inspect_with() {
+        let mut monkeys = parse_file(&String::from("data/test_input"));
+        monkeys[0].inspect_with(&Relief { divisor: 3 }).unwrap();
+        assert_eq!(monkeys[0].items_worry, [500, 620]);
+        monkeys[2].inspect_with(&Modulo { modulus: 1000 }).unwrap();
+        assert_eq!(monkeys[2].items_worry, [241, 600, 409]);
+        assert_eq!(monkeys[2].inspection_counter, 3);
+        monkeys[2].items_worry = VecDeque::from([u64::MAX]);
+        let error = monkeys[2]
+            .inspect_with(&Modulo { modulus: 1000 })
+            .unwrap_err();
+        let expected = format!(
+            "Operation 'new = old * old' overflows for old = {}",
+            u64::MAX
+        );
+        assert_eq!(error, expected);
+    }
+
+    #[test]
+    fn test_count_inspections() {
+        let monkeys = parse_file(&String::from("data/test_input"));
+        assert_eq!(get_lcm(&monkeys), 96577);
+        assert_eq!(count_inspections(&monkeys, &0), Ok(vec![0, 0, 0, 0]));
+        assert_eq!(count_inspections(&monkeys, &1), Ok(vec![2, 4, 3, 6]));
+        assert_eq!(count_inspections(&monkeys, &20), Ok(vec![99, 97, 8, 103]));
+        // Fast-forwarding gives the same counts as simulating every round
+        let expected = count_inspections(&monkeys, &10000).unwrap();
+        let mut simulated = parse_file(&String::from("data/test_input"));
+        let modulo = Modulo { modulus: 96577 };
+        simulate(&mut simulated, &10000, &modulo).unwrap();
+        let inspections: Vec<u64> = simulated.iter().map(|m| m.inspection_counter).collect();
+        assert_eq!(inspections, expected);
+        assert_eq!(expected, vec![52166, 47830, 1938, 52013]);
+        let inspections = count_inspections(&monkeys, &1_000_000_000_000).unwrap();
+        assert_eq!(inspections.iter().sum::<u64>(), 15395285087711);
+    }
+
+    #[test]
+    fn test_big_uint() {
+        let max = BigUint::from_u64(u64::MAX);
+        let one = BigUint::from_u64(1);
+        assert_eq!(max.add(&one).to_string(), "18446744073709551616");
+        assert_eq!(max.add(&one).to_u64(), None);
+        let square = max.mul(&max);
+        assert_eq!(
+            square.to_string(),
+            "340282366920938463426481119284349108225"
+        );
+        assert_eq!(
+            square.checked_div_rem(&max),
+            Some((max.clone(), BigUint::from_u64(0)))
+        );
+        let (quotient, remainder) = square.add(&one).checked_div_rem(&max.add(&one)).unwrap();
+        assert_eq!(quotient.to_string(), "18446744073709551614");
+        assert_eq!(remainder.to_string(), "2");
+        assert_eq!(square.rem_u64(&1_000_000_007), 114944269);
+        assert_eq!(one.checked_sub(&max), None);
+        assert_eq!(square.checked_sub(&square).map(|n| n.is_zero()), Some(true));
+        assert_eq!(max.checked_div_rem(&BigUint::from_u64(0)), None);
+        let expr = Expr::parse("old * old + 1").unwrap();
+        assert_eq!(expr.evaluate(&max), Ok(square.add(&one)));
+    }
+
+    #[test]
+    fn test_exact_worry() {
+        let monkeys = parse_file(&String::from("data/test_input"));
+        let game = simulate_exact(&monkeys, &20, &3).unwrap();
+        assert_eq!(game.inspections, vec![101, 95, 7, 105]);
+        assert_eq!(game.items[0], [10, 12, 14, 26, 34].map(BigUint::from_u64));
+        let game = simulate_exact(&monkeys, &20, &1).unwrap();
+        assert_eq!(game.inspections, vec![99, 97, 8, 103]);
+        assert_eq!(validate_modulo(&monkeys, &20), Ok(true));
+        assert!(simulate_exact(&monkeys, &1, &0).is_err());
+    }
+
+    #[test]
+    fn test_find_overflow() {
+        let mut monkeys = parse_file(&String::from("data/test_input"));
+        let overflow = find_overflow(&mut monkeys, &20, &Relief { divisor: 1 });
+        let expected = Overflow {
+            round: 13,
+            monkey: 0,
+            worry: 13988703546165100909,
+        };
+        assert_eq!(overflow, Ok(Some(expected)));
+        let mut monkeys = parse_file(&String::from("data/test_input"));
+        assert_eq!(
+            find_overflow(&mut monkeys, &20, &Relief { divisor: 3 }),
+            Ok(None)
+        );
+        // Other failures aren't reported as overflows
+        let mut monkeys = parse_file(&String::from("data/test_input"));
+        monkeys[0].operation = Expr::parse("old - 100").unwrap();
+        let error = find_overflow(&mut monkeys, &20, &Relief { divisor: 1 }).unwrap_err();
+        assert_eq!(
+            error,
+            "Monkey 0 in round 1: Operation 'new = old - 100' goes below zero for old = 79"
+        );
+        monkeys[0].operation = Expr::parse("5 / old").unwrap();
+        monkeys[0].items_worry = VecDeque::from([0]);
+        let error = simulate_exact(&monkeys, &1, &1).unwrap_err();
+        assert_eq!(error, "Monkey 0 divides by zero in round 1");
+    }
+
+    #[test]
+    fn test_count_inspections_operators() {
+        // Subtractions are carried out modulo the LCM too
+        let mut monkeys = parse_file(&String::from("data/test_input"));
+        monkeys[3].operation = Expr::parse("old * 2 - 1").unwrap();
+        let expected = simulate_exact(&monkeys, &20, &1).unwrap().inspections;
+        assert_eq!(count_inspections(&monkeys, &20), Ok(expected));
+        // Fast-forwarding doesn't overflow silently
+        let error = count_inspections(&monkeys, &u64::MAX).unwrap_err();
+        let expected = format!("Inspections of monkey 0 overflow after {} rounds", u64::MAX);
+        assert_eq!(error, expected);
+        // Divisions can't
+        monkeys[0].operation = Expr::parse("old / 2 + 7").unwrap();
+        let error = count_inspections(&monkeys, &20).unwrap_err();
+        let expected =
+            "Operation 'new = old / 2 + 7' of monkey 0 divides, so worry levels can't be reduced";
+        assert_eq!(error, expected);
+        assert!(validate_modulo(&monkeys, &20).is_err());
+    }
+
     #[test]
     fn test_part1() {
         let fname = String::from("data/test_input");
@@ -21,60 +192,508 @@ mod tests {
     }
 }
 
-#[derive(Debug)]
-pub struct Monkey {
-    pub items_worry: Vec<u64>, // stack of items, each element is its worry level
-    pub operator: String,      // operation that should be carried out ("+", "-", "*" "**")
-    pub operation_value: u64,  // value associated with the operation. For old set it to 0.
-    pub divisible_by: u64,     // test if the worry level is divisible by this value
-    pub throw_to_if_pass: usize, // if test pass, throw the item the monkey given by this value
-    pub throw_to_if_fail: usize, // if test fail, throw the item to monkey given by this value
-    pub inspection_counter: u64, // counter for the inspections carried out by the monkey
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Operator {
+    Add,
+    Sub,
+    Mul,
+    Div,
 }
 
-impl Monkey {
-    pub fn inspect(&mut self) {
-        // Inspect the objects that the monkey is holding
+impl Display for Operator {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Operator::Add => write!(f, "+"),
+            Operator::Sub => write!(f, "-"),
+            Operator::Mul => write!(f, "*"),
+            Operator::Div => write!(f, "/"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EvalError {
+    Overflow,       // the result doesn't fit in the worry level type
+    BelowZero,      // the result is negative
+    DivisionByZero, // the operation divides by zero
+}
+
+impl Display for EvalError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            EvalError::Overflow => write!(f, "overflows"),
+            EvalError::BelowZero => write!(f, "goes below zero"),
+            EvalError::DivisionByZero => write!(f, "divides by zero"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Expr {
+    Old,
+    Literal(u64),
+    Operation(Box<Expr>, Operator, Box<Expr>),
+}
+
+impl Display for Expr {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Expr::Old => write!(f, "old"),
+            Expr::Literal(value) => write!(f, "{}", value),
+            Expr::Operation(left, operator, right) => {
+                write!(f, "{} {} {}", left, operator, right)
+            }
+        }
+    }
+}
+
+impl Expr {
+    pub fn parse(text: &str) -> Result<Expr, String> {
+        // Parse the right hand side of an operation (e.g. old * 19 or 3 + old)
         //
-        // Modify the worry level of each of the items that the monkey is holding.
-        // This method divides the worry level by 3 after each operation.
-        self.items_worry.iter_mut().for_each(|item_worry| {
-            *item_worry = match self.operator.as_str() {
-                "+" => *item_worry + self.operation_value,
-                "-" => *item_worry - self.operation_value,
-                "*" => *item_worry * self.operation_value,
-                "**" => *item_worry * *item_worry,
-                _ => panic!("Invalid operation '{}'", self.operator),
-            };
-            *item_worry /= 3;
-            self.inspection_counter += 1;
-        });
+        // Operations are evaluated from left to right, with * and / taking precedence over + and
+        // -.
+        let tokens = tokenize(text)?;
+        let mut position = 0;
+        let expr = parse_sum(&tokens, &mut position)?;
+        if position < tokens.len() {
+            return Err(format!("Unexpected '{}' in '{}'", tokens[position], text));
+        }
+        Ok(expr)
+    }
+
+    pub fn evaluate<T: WorryLevel>(&self, old: &T) -> Result<T, EvalError> {
+        // Compute the new worry level, or the reason why it can't be computed
+        match self {
+            Expr::Old => Ok(old.clone()),
+            Expr::Literal(value) => Ok(old.with_value(*value)),
+            Expr::Operation(left, operator, right) => {
+                let (left, right) = (left.evaluate(old)?, right.evaluate(old)?);
+                match operator {
+                    Operator::Add => left.add_checked(&right).ok_or(EvalError::Overflow),
+                    Operator::Sub => left.sub_checked(&right).ok_or(EvalError::BelowZero),
+                    Operator::Mul => left.mul_checked(&right).ok_or(EvalError::Overflow),
+                    Operator::Div => left.div_checked(&right).ok_or(EvalError::DivisionByZero),
+                }
+            }
+        }
+    }
+
+    pub fn contains(&self, operator: &Operator) -> bool {
+        match self {
+            Expr::Old | Expr::Literal(_) => false,
+            Expr::Operation(left, op, right) => {
+                op == operator || left.contains(operator) || right.contains(operator)
+            }
+        }
+    }
+}
+
+fn tokenize(text: &str) -> Result<Vec<String>, String> {
+    // Split an operation into numbers, old and operators
+    let mut tokens: Vec<String> = vec![];
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            ' ' => (),
+            '+' | '-' | '*' | '/' => tokens.push(c.to_string()),
+            _ if c.is_ascii_alphanumeric() => {
+                let mut token = c.to_string();
+                while let Some(next) = chars.next_if(|next| next.is_ascii_alphanumeric()) {
+                    token.push(next);
+                }
+                tokens.push(token);
+            }
+            _ => return Err(format!("Invalid character '{}' in '{}'", c, text)),
+        }
+    }
+    Ok(tokens)
+}
+
+fn parse_operand(tokens: &[String], position: &mut usize) -> Result<Expr, String> {
+    let token = match tokens.get(*position) {
+        Some(token) => token,
+        None => return Err(String::from("Missing operand")),
+    };
+    *position += 1;
+    match token.as_str() {
+        "old" => Ok(Expr::Old),
+        _ => match token.parse() {
+            Ok(value) => Ok(Expr::Literal(value)),
+            Err(_) => Err(format!("Invalid operand '{}'", token)),
+        },
+    }
+}
+
+fn parse_operator(tokens: &[String], position: &usize, operators: &[Operator]) -> Option<Operator> {
+    // Get the operator at the given position if it's one of the given ones
+    let operator = match tokens.get(*position).map(|token| token.as_str()) {
+        Some("+") => Operator::Add,
+        Some("-") => Operator::Sub,
+        Some("*") => Operator::Mul,
+        Some("/") => Operator::Div,
+        _ => return None,
+    };
+    operators.contains(&operator).then_some(operator)
+}
+
+fn parse_product(tokens: &[String], position: &mut usize) -> Result<Expr, String> {
+    let mut expr = parse_operand(tokens, position)?;
+    while let Some(operator) = parse_operator(tokens, position, &[Operator::Mul, Operator::Div]) {
+        *position += 1;
+        let right = parse_operand(tokens, position)?;
+        expr = Expr::Operation(Box::new(expr), operator, Box::new(right));
+    }
+    Ok(expr)
+}
+
+fn parse_sum(tokens: &[String], position: &mut usize) -> Result<Expr, String> {
+    let mut expr = parse_product(tokens, position)?;
+    while let Some(operator) = parse_operator(tokens, position, &[Operator::Add, Operator::Sub]) {
+        *position += 1;
+        let right = parse_product(tokens, position)?;
+        expr = Expr::Operation(Box::new(expr), operator, Box::new(right));
+    }
+    Ok(expr)
+}
+
+pub trait WorryLevel: Clone {
+    // Worry levels that operations can be evaluated on
+    fn with_value(&self, value: u64) -> Self; // worry level of the same kind with another value
+    fn add_checked(&self, other: &Self) -> Option<Self>;
+    fn sub_checked(&self, other: &Self) -> Option<Self>;
+    fn mul_checked(&self, other: &Self) -> Option<Self>;
+    fn div_checked(&self, other: &Self) -> Option<Self>;
+    fn rem_u64(&self, divisor: &u64) -> u64;
+}
+
+impl WorryLevel for u64 {
+    fn with_value(&self, value: u64) -> Self {
+        value
+    }
+
+    fn add_checked(&self, other: &Self) -> Option<Self> {
+        self.checked_add(*other)
+    }
+
+    fn sub_checked(&self, other: &Self) -> Option<Self> {
+        self.checked_sub(*other)
+    }
+
+    fn mul_checked(&self, other: &Self) -> Option<Self> {
+        self.checked_mul(*other)
+    }
+
+    fn div_checked(&self, other: &Self) -> Option<Self> {
+        self.checked_div(*other)
+    }
+
+    fn rem_u64(&self, divisor: &u64) -> u64 {
+        self % divisor
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BigUint {
+    limbs: Vec<u32>, // digits in base 2^32, least significant first, without leading zeros
+}
+
+impl BigUint {
+    pub fn from_u64(value: u64) -> Self {
+        BigUint::from_limbs(vec![value as u32, (value >> 32) as u32])
+    }
+
+    fn from_limbs(mut limbs: Vec<u32>) -> Self {
+        while limbs.last() == Some(&0) {
+            limbs.pop();
+        }
+        BigUint { limbs }
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.limbs.is_empty()
+    }
+
+    pub fn to_u64(&self) -> Option<u64> {
+        match self.limbs.as_slice() {
+            [] => Some(0),
+            [low] => Some(*low as u64),
+            [low, high] => Some(*low as u64 | (*high as u64) << 32),
+            _ => None,
+        }
+    }
+
+    fn get_n_bits(&self) -> usize {
+        match self.limbs.last() {
+            Some(last) => 32 * self.limbs.len() - last.leading_zeros() as usize,
+            None => 0,
+        }
+    }
+
+    fn get_bit(&self, index: &usize) -> bool {
+        (self.limbs[index / 32] >> (index % 32)) & 1 == 1
+    }
+
+    fn shift_in(&self, bit: bool) -> BigUint {
+        // Shift the number one bit to the left, setting the new lowest bit
+        let mut limbs = Vec::with_capacity(self.limbs.len() + 1);
+        let mut carry = bit as u32;
+        for limb in self.limbs.iter() {
+            limbs.push(limb << 1 | carry);
+            carry = limb >> 31;
+        }
+        limbs.push(carry);
+        BigUint::from_limbs(limbs)
+    }
+
+    pub fn add(&self, other: &BigUint) -> BigUint {
+        let mut limbs = vec![];
+        let mut carry = 0;
+        for i in 0..self.limbs.len().max(other.limbs.len()) {
+            let a = *self.limbs.get(i).unwrap_or(&0) as u64;
+            let b = *other.limbs.get(i).unwrap_or(&0) as u64;
+            let sum = a + b + carry;
+            limbs.push(sum as u32);
+            carry = sum >> 32;
+        }
+        limbs.push(carry as u32);
+        BigUint::from_limbs(limbs)
+    }
+
+    pub fn checked_sub(&self, other: &BigUint) -> Option<BigUint> {
+        // Subtract another number, or return None if it's larger
+        if self < other {
+            return None;
+        }
+        let mut limbs = vec![];
+        let mut borrow = 0;
+        for (i, limb) in self.limbs.iter().enumerate() {
+            let difference = *limb as i64 - *other.limbs.get(i).unwrap_or(&0) as i64 - borrow;
+            borrow = (difference < 0) as i64;
+            limbs.push((difference + (borrow << 32)) as u32);
+        }
+        Some(BigUint::from_limbs(limbs))
+    }
+
+    pub fn mul(&self, other: &BigUint) -> BigUint {
+        let mut limbs = vec![0; self.limbs.len() + other.limbs.len()];
+        for (i, a) in self.limbs.iter().enumerate() {
+            let mut carry = 0;
+            for (j, b) in other.limbs.iter().enumerate() {
+                let product = limbs[i + j] as u64 + *a as u64 * *b as u64 + carry;
+                limbs[i + j] = product as u32;
+                carry = product >> 32;
+            }
+            limbs[i + other.limbs.len()] = carry as u32;
+        }
+        BigUint::from_limbs(limbs)
+    }
+
+    pub fn div_rem_u32(&self, divisor: &u32) -> (BigUint, u32) {
+        // Divide by a small number, returning the quotient and the remainder
+        let mut limbs = vec![0; self.limbs.len()];
+        let mut remainder: u64 = 0;
+        for (i, limb) in self.limbs.iter().enumerate().rev() {
+            let current = remainder << 32 | *limb as u64;
+            limbs[i] = (current / *divisor as u64) as u32;
+            remainder = current % *divisor as u64;
+        }
+        (BigUint::from_limbs(limbs), remainder as u32)
     }
 
-    pub fn inspect_with_modulo(&mut self, factor: &u64) {
+    pub fn checked_div_rem(&self, divisor: &BigUint) -> Option<(BigUint, BigUint)> {
+        // Divide by another number, returning the quotient and the remainder, or None if the
+        // divisor is zero
+        //
+        // Large divisors are handled with binary long division.
+        match divisor.limbs.as_slice() {
+            [] => return None,
+            [small] => {
+                let (quotient, remainder) = self.div_rem_u32(small);
+                return Some((quotient, BigUint::from_u64(remainder as u64)));
+            }
+            _ => (),
+        }
+        let mut quotient = vec![0; self.limbs.len()];
+        let mut remainder = BigUint { limbs: vec![] };
+        for i in (0..self.get_n_bits()).rev() {
+            remainder = remainder.shift_in(self.get_bit(&i));
+            if remainder >= *divisor {
+                remainder = remainder.checked_sub(divisor).unwrap();
+                quotient[i / 32] |= 1 << (i % 32);
+            }
+        }
+        Some((BigUint::from_limbs(quotient), remainder))
+    }
+}
+
+impl Ord for BigUint {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.limbs
+            .len()
+            .cmp(&other.limbs.len())
+            .then_with(|| self.limbs.iter().rev().cmp(other.limbs.iter().rev()))
+    }
+}
+
+impl PartialOrd for BigUint {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Display for BigUint {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        // Write the number in chunks of 9 decimal digits, starting from the least significant
+        if self.is_zero() {
+            return write!(f, "0");
+        }
+        let mut chunks = vec![];
+        let mut number = self.clone();
+        while !number.is_zero() {
+            let (quotient, chunk) = number.div_rem_u32(&1_000_000_000);
+            chunks.push(chunk);
+            number = quotient;
+        }
+        write!(f, "{}", chunks.last().unwrap())?;
+        for chunk in chunks.iter().rev().skip(1) {
+            write!(f, "{:09}", chunk)?;
+        }
+        Ok(())
+    }
+}
+
+impl WorryLevel for BigUint {
+    fn with_value(&self, value: u64) -> Self {
+        BigUint::from_u64(value)
+    }
+
+    fn add_checked(&self, other: &Self) -> Option<Self> {
+        Some(self.add(other))
+    }
+
+    fn sub_checked(&self, other: &Self) -> Option<Self> {
+        self.checked_sub(other)
+    }
+
+    fn mul_checked(&self, other: &Self) -> Option<Self> {
+        Some(self.mul(other))
+    }
+
+    fn div_checked(&self, other: &Self) -> Option<Self> {
+        self.checked_div_rem(other).map(|(quotient, _)| quotient)
+    }
+
+    fn rem_u64(&self, divisor: &u64) -> u64 {
+        let mut remainder: u128 = 0;
+        for limb in self.limbs.iter().rev() {
+            remainder = (remainder << 32 | *limb as u128) % *divisor as u128;
+        }
+        remainder as u64
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReducedWorry {
+    pub value: u64,   // worry level modulo the modulus
+    pub modulus: u64, // common multiple of the 'divisible_by' values of every monkey
+}
+
+impl WorryLevel for ReducedWorry {
+    // Worry levels kept modulo a common multiple of the 'divisible_by' values, which preserves
+    // the result of every test. Divisions can't be carried out on them.
+    fn with_value(&self, value: u64) -> Self {
+        ReducedWorry {
+            value: value % self.modulus,
+            modulus: self.modulus,
+        }
+    }
+
+    fn add_checked(&self, other: &Self) -> Option<Self> {
+        let value = (self.value as u128 + other.value as u128) % self.modulus as u128;
+        Some(self.with_value(value as u64))
+    }
+
+    fn sub_checked(&self, other: &Self) -> Option<Self> {
+        let value = self.value as u128 + self.modulus as u128 - other.value as u128;
+        Some(self.with_value((value % self.modulus as u128) as u64))
+    }
+
+    fn mul_checked(&self, other: &Self) -> Option<Self> {
+        let value = (self.value as u128 * other.value as u128) % self.modulus as u128;
+        Some(self.with_value(value as u64))
+    }
+
+    fn div_checked(&self, _other: &Self) -> Option<Self> {
+        None
+    }
+
+    fn rem_u64(&self, divisor: &u64) -> u64 {
+        self.value % divisor
+    }
+}
+
+pub trait WorryReduction {
+    // Reduce the worry level of an item after it has been inspected
+    fn reduce(&self, worry: u64) -> u64;
+}
+
+pub struct Relief {
+    pub divisor: u64, // the worry level is divided by this value after each inspection
+}
+
+impl WorryReduction for Relief {
+    fn reduce(&self, worry: u64) -> u64 {
+        worry / self.divisor
+    }
+}
+
+pub struct Modulo {
+    pub modulus: u64, // common multiple of the 'divisible_by' values of every monkey
+}
+
+impl WorryReduction for Modulo {
+    fn reduce(&self, worry: u64) -> u64 {
+        worry % self.modulus
+    }
+}
+
+#[derive(Debug)]
+pub struct Monkey {
+    pub items_worry: VecDeque<u64>, // queue of items, each element is its worry level
+    pub operation: Expr,            // operation that computes the new worry level from the old one
+    pub divisible_by: u64,          // test if the worry level is divisible by this value
+    pub throw_to_if_pass: usize,    // if test pass, throw the item the monkey given by this value
+    pub throw_to_if_fail: usize,    // if test fail, throw the item to monkey given by this value
+    pub inspection_counter: u64,    // counter for the inspections carried out by the monkey
+}
+
+impl Monkey {
+    pub fn inspect_with(&mut self, reduction: &dyn WorryReduction) -> Result<(), String> {
         // Inspect the objects that the monkey is holding
         //
-        // Modify the worry level of each of the items that the monkey is holding.
-        // This method reduces the worry levels by applying the modulo of the minimim common
-        // multiple of all the 'divisible_by' values in the whole set of monkeys.
-        self.items_worry.iter_mut().for_each(|item_worry| {
-            *item_worry = match self.operator.as_str() {
-                "+" => *item_worry + self.operation_value,
-                "-" => *item_worry - self.operation_value,
-                "*" => *item_worry * self.operation_value,
-                "**" => *item_worry * *item_worry,
-                _ => panic!("Invalid operation '{}'", self.operator),
+        // Modify the worry level of each of the items that the monkey is holding, reducing it
+        // with the given strategy after each operation. Return an error if the operation can't
+        // be computed.
+        for item_worry in self.items_worry.iter_mut() {
+            *item_worry = match self.operation.evaluate(item_worry) {
+                Ok(worry) => reduction.reduce(worry),
+                Err(why) => {
+                    return Err(format!(
+                        "Operation 'new = {}' {} for old = {}",
+                        self.operation, why, item_worry
+                    ))
+                }
             };
-            *item_worry %= *factor;
             self.inspection_counter += 1;
-        });
+        }
+        Ok(())
     }
 
     pub fn throw(&mut self) -> Vec<(usize, u64)> {
         // Return a vec with monkey number and the worry level of the item being thrown to
         let mut throws = vec![];
-        while !self.items_worry.is_empty() {
-            let item = self.items_worry.remove(0);
+        while let Some(item) = self.items_worry.pop_front() {
             let receiver = match item % self.divisible_by {
                 0 => self.throw_to_if_pass,
                 _ => self.throw_to_if_fail,
@@ -88,22 +707,21 @@ impl Monkey {
 fn read_file(fname: &String) -> String {
     // Open file
     let path = Path::new(&fname);
-    let mut file = match File::open(&path) {
+    let mut file = match File::open(path) {
         Err(why) => panic!("couldn't open {}: {}", fname, why),
         Ok(file) => file,
     };
     // Read file
     let mut content = String::new();
-    match file.read_to_string(&mut content) {
-        Err(why) => panic!("couldn't read {}: {}", fname, why),
-        Ok(_) => (),
+    if let Err(why) = file.read_to_string(&mut content) {
+        panic!("couldn't read {}: {}", fname, why)
     };
     content
 }
 
 fn parse_file(fname: &String) -> Vec<Monkey> {
     // Read data file
-    let data = read_file(&fname);
+    let data = read_file(fname);
     // Parse lines
     let mut monkeys = vec![];
     for monkey_lines in data.split("Monkey").filter(|l| !l.is_empty()) {
@@ -119,7 +737,7 @@ fn parse_single_monkey(monkey_lines: &str) -> Monkey {
     lines.next();
     // Read line with the worry levels of the items that the monkey is holding
     let line = lines.next().unwrap();
-    let items_worry: Vec<u64> = line
+    let items_worry: VecDeque<u64> = line
         .split(":")
         .last()
         .unwrap()
@@ -128,13 +746,9 @@ fn parse_single_monkey(monkey_lines: &str) -> Monkey {
         .collect();
     // Read line with the operation that the monkey performs
     let line = lines.next().unwrap();
-    let parts: Vec<&str> = line.split("=").last().unwrap().split_whitespace().collect();
-    let (operator, operation_value) = match *parts.last().unwrap() {
-        "old" => (String::from("**"), 0),
-        _ => (
-            String::from(parts[1]),
-            parts.last().unwrap().parse().unwrap(),
-        ),
+    let operation = match Expr::parse(line.split_once('=').map_or("", |(_, rhs)| rhs)) {
+        Ok(operation) => operation,
+        Err(why) => panic!("Invalid operation in '{}': {}", line.trim(), why),
     };
     // Read line with the test that the monkey performs
     let line = lines.next().unwrap();
@@ -148,8 +762,7 @@ fn parse_single_monkey(monkey_lines: &str) -> Monkey {
     // Return the monkey
     Monkey {
         items_worry,
-        operator,
-        operation_value,
+        operation,
         divisible_by,
         throw_to_if_pass,
         throw_to_if_fail,
@@ -157,51 +770,341 @@ fn parse_single_monkey(monkey_lines: &str) -> Monkey {
     }
 }
 
-fn solve_part1(fname: &String) -> u64 {
-    // Parse input file and get a vec of the monkeys
-    let mut monkeys = parse_file(fname);
-    // Run 20 rounds of the monkeys' game
-    for _ in 0..20 {
+pub fn simulate(
+    monkeys: &mut [Monkey],
+    rounds: &u64,
+    reduction: &dyn WorryReduction,
+) -> Result<(), String> {
+    // Play the given number of rounds of the monkeys' game
+    for _ in 0..*rounds {
         for i in 0..monkeys.len() {
-            monkeys[i].inspect();
+            monkeys[i].inspect_with(reduction)?;
             let throws = monkeys[i].throw();
             for (receiver, item) in throws {
-                monkeys[receiver].items_worry.push(item);
+                monkeys[receiver].items_worry.push_back(item);
             }
         }
     }
-    // // Compute monkey business
-    let mut inspections: Vec<u64> = monkeys.iter().map(|m| m.inspection_counter).collect();
-    inspections.sort();
-    inspections.reverse();
-    inspections[0] * inspections[1]
+    Ok(())
 }
 
-fn solve_part2(fname: &String) -> u64 {
-    // Parse input file and get a vec of the monkeys
-    let mut monkeys = parse_file(fname);
-    // Get the minimum common multiple of all the 'divisible_by' primes in the group of monkeys
-    let factor = monkeys.iter().map(|m| m.divisible_by).product();
-    // Run 10000 rounds of the monkeys' game
-    for _ in 0..10000 {
+fn get_gcd(a: u64, b: u64) -> u64 {
+    match b {
+        0 => a,
+        _ => get_gcd(b, a % b),
+    }
+}
+
+pub fn get_lcm(monkeys: &[Monkey]) -> u64 {
+    // Get the least common multiple of all the 'divisible_by' values in the group of monkeys
+    monkeys.iter().fold(1, |lcm, m| {
+        lcm / get_gcd(lcm, m.divisible_by) * m.divisible_by
+    })
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ItemState {
+    pub holder: usize, // monkey holding the item at the start of the round
+    pub worry: u64,    // worry level of the item, modulo the LCM of the 'divisible_by' values
+}
+
+pub fn get_item_states(monkeys: &[Monkey]) -> Vec<ItemState> {
+    // Get the state of every item held by the monkeys
+    let mut states = vec![];
+    for (holder, monkey) in monkeys.iter().enumerate() {
+        for worry in monkey.items_worry.iter() {
+            states.push(ItemState {
+                holder,
+                worry: *worry,
+            });
+        }
+    }
+    states
+}
+
+fn play_item_round(
+    monkeys: &[Monkey],
+    state: &ItemState,
+    reduction: &Modulo,
+    inspections: &mut [u64],
+) -> Result<ItemState, String> {
+    // Follow an item during a single round and count the inspections it goes through
+    //
+    // Monkeys take turns in order, so an item thrown to a monkey that comes later in the round is
+    // inspected again during the same round.
+    let mut state = *state;
+    loop {
+        let monkey = &monkeys[state.holder];
+        let old = ReducedWorry {
+            value: state.worry,
+            modulus: reduction.modulus,
+        };
+        let worry = match monkey.operation.evaluate(&old) {
+            Ok(worry) => worry.value,
+            Err(why) => return Err(format!("Monkey {} {}", state.holder, why)),
+        };
+        inspections[state.holder] += 1;
+        let receiver = match worry % monkey.divisible_by {
+            0 => monkey.throw_to_if_pass,
+            _ => monkey.throw_to_if_fail,
+        };
+        let finished = receiver <= state.holder;
+        state = ItemState {
+            holder: receiver,
+            worry,
+        };
+        if finished {
+            return Ok(state);
+        }
+    }
+}
+
+pub fn count_inspections(monkeys: &[Monkey], rounds: &u64) -> Result<Vec<u64>, String> {
+    // Count the inspections of every monkey after the given number of rounds, keeping the worry
+    // levels modulo the LCM of the 'divisible_by' values
+    //
+    // Items don't interact with each other, and each one of them can only be in a finite number
+    // of states at the start of a round, so they eventually repeat a cycle of states. Follow
+    // every item until it starts repeating and fast-forward through the rest of the rounds.
+    //
+    // Divisions can't be carried out on reduced worry levels, so return an error if any
+    // operation has one.
+    for (i, monkey) in monkeys.iter().enumerate() {
+        if monkey.operation.contains(&Operator::Div) {
+            return Err(format!(
+                "Operation 'new = {}' of monkey {} divides, so worry levels can't be reduced",
+                monkey.operation, i
+            ));
+        }
+    }
+    let reduction = Modulo {
+        modulus: get_lcm(monkeys),
+    };
+    let mut inspections = vec![0; monkeys.len()];
+    for item in get_item_states(monkeys) {
+        let mut state = ItemState {
+            holder: item.holder,
+            worry: item.worry % reduction.modulus,
+        };
+        // Round in which each state was seen and inspections before each round
+        let mut seen: HashMap<ItemState, u64> = HashMap::new();
+        let mut history: Vec<Vec<u64>> = vec![vec![0; monkeys.len()]];
+        let mut round = 0;
+        while round < *rounds {
+            if let Some(start) = seen.get(&state) {
+                // Add the inspections of the remaining full cycles and of the rest of the rounds
+                let (start, length) = (*start, round - start);
+                let (n_cycles, remainder) = ((rounds - round) / length, (rounds - round) % length);
+                let end = &history[round as usize];
+                let cycle_start = &history[start as usize];
+                let partial = &history[(start + remainder) as usize];
+                for i in 0..monkeys.len() {
+                    inspections[i] = (end[i] - cycle_start[i])
+                        .checked_mul(n_cycles)
+                        .and_then(|total| total.checked_add(end[i]))
+                        .and_then(|total| total.checked_add(partial[i] - cycle_start[i]))
+                        .and_then(|total| total.checked_add(inspections[i]))
+                        .ok_or(format!(
+                            "Inspections of monkey {} overflow after {} rounds",
+                            i, rounds
+                        ))?;
+                }
+                break;
+            }
+            seen.insert(state, round);
+            let mut counts = history[round as usize].clone();
+            state = play_item_round(monkeys, &state, &reduction, &mut counts)?;
+            history.push(counts);
+            round += 1;
+        }
+        if round == *rounds {
+            for (total, count) in inspections.iter_mut().zip(history[round as usize].iter()) {
+                *total += count;
+            }
+        }
+    }
+    Ok(inspections)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExactGame {
+    pub items: Vec<VecDeque<BigUint>>, // worry levels of the items held by each monkey
+    pub inspections: Vec<u64>,         // inspections carried out by each monkey
+}
+
+pub fn simulate_exact(
+    monkeys: &[Monkey],
+    rounds: &u64,
+    divisor: &u64,
+) -> Result<ExactGame, String> {
+    // Play the given number of rounds keeping the exact worry levels of the items, dividing them
+    // by the given divisor after each inspection (use 1 to keep them unreduced)
+    //
+    // Worry levels can grow exponentially without reduction, so this is only practical for a
+    // small number of rounds.
+    if *divisor == 0 {
+        return Err(String::from("Worry levels can't be divided by zero"));
+    }
+    let divisor = BigUint::from_u64(*divisor);
+    let mut game = ExactGame {
+        items: monkeys
+            .iter()
+            .map(|m| {
+                m.items_worry
+                    .iter()
+                    .map(|w| BigUint::from_u64(*w))
+                    .collect()
+            })
+            .collect(),
+        inspections: vec![0; monkeys.len()],
+    };
+    for round in 1..=*rounds {
+        for (i, monkey) in monkeys.iter().enumerate() {
+            while let Some(worry) = game.items[i].pop_front() {
+                let worry = match monkey.operation.evaluate(&worry) {
+                    Ok(worry) => worry.div_checked(&divisor).unwrap(),
+                    Err(why) => return Err(format!("Monkey {} {} in round {}", i, why, round)),
+                };
+                game.inspections[i] += 1;
+                let receiver = match worry.rem_u64(&monkey.divisible_by) {
+                    0 => monkey.throw_to_if_pass,
+                    _ => monkey.throw_to_if_fail,
+                };
+                game.items[receiver].push_back(worry);
+            }
+        }
+    }
+    Ok(game)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Overflow {
+    pub round: u64,    // round in which the overflow happens, starting from 1
+    pub monkey: usize, // monkey whose operation overflows
+    pub worry: u64,    // worry level of the item before the operation
+}
+
+pub fn find_overflow(
+    monkeys: &mut [Monkey],
+    rounds: &u64,
+    reduction: &dyn WorryReduction,
+) -> Result<Option<Overflow>, String> {
+    // Play the given number of rounds and find the first inspection whose result doesn't fit in
+    // a u64, if any
+    //
+    // Return an error if an operation fails for any other reason (going below zero or dividing
+    // by zero), since the game can't go on.
+    for round in 1..=*rounds {
         for i in 0..monkeys.len() {
-            monkeys[i].inspect_with_modulo(&factor);
+            let monkey = &monkeys[i];
+            let overflowing = monkey
+                .items_worry
+                .iter()
+                .find(|worry| monkey.operation.evaluate(*worry) == Err(EvalError::Overflow));
+            if let Some(worry) = overflowing {
+                return Ok(Some(Overflow {
+                    round,
+                    monkey: i,
+                    worry: *worry,
+                }));
+            }
+            if let Err(why) = monkeys[i].inspect_with(reduction) {
+                return Err(format!("Monkey {} in round {}: {}", i, round, why));
+            }
             let throws = monkeys[i].throw();
             for (receiver, item) in throws {
-                monkeys[receiver].items_worry.push(item);
+                monkeys[receiver].items_worry.push_back(item);
             }
         }
     }
-    // // Compute monkey business
-    let mut inspections: Vec<u64> = monkeys.iter().map(|m| m.inspection_counter).collect();
+    Ok(None)
+}
+
+pub fn validate_modulo(monkeys: &[Monkey], rounds: &u64) -> Result<bool, String> {
+    // Check that keeping worry levels modulo the LCM of the 'divisible_by' values gives the same
+    // inspections as keeping the exact worry levels
+    let exact = simulate_exact(monkeys, rounds, &1)?;
+    Ok(count_inspections(monkeys, rounds)? == exact.inspections)
+}
+
+fn get_monkey_business(inspections: &[u64]) -> u128 {
+    // Multiply the number of inspections of the two most active monkeys
+    let mut inspections = inspections.to_vec();
     inspections.sort();
     inspections.reverse();
-    inspections[0] * inspections[1]
+    inspections[0] as u128 * inspections[1] as u128
+}
+
+fn solve_part1(fname: &String) -> u128 {
+    // Parse input file and get a vec of the monkeys
+    let mut monkeys = parse_file(fname);
+    // Run 20 rounds of the monkeys' game
+    if let Err(why) = simulate(&mut monkeys, &20, &Relief { divisor: 3 }) {
+        panic!("{}", why)
+    }
+    let inspections: Vec<u64> = monkeys.iter().map(|m| m.inspection_counter).collect();
+    get_monkey_business(&inspections)
+}
+
+fn solve_part2(fname: &String) -> u128 {
+    // Parse input file and get a vec of the monkeys
+    let monkeys = parse_file(fname);
+    // Count the inspections after 10000 rounds of the monkeys' game
+    match count_inspections(&monkeys, &10000) {
+        Ok(inspections) => get_monkey_business(&inspections),
+        Err(why) => panic!("{}", why),
+    }
 }
 
 fn main() {
     let fname = String::from("data/input");
 
+    // compute the monkey business after any number of rounds if asked to:
+    //   cargo run -- rounds <n>
+    //   cargo run -- exact <n> [divisor]
+    //   cargo run -- overflow <n> [divisor]
+    //   cargo run -- validate <n>
+    let args: Vec<String> = env::args().collect();
+    let command = args.get(1).map(|arg| arg.as_str());
+    if let Some("rounds" | "exact" | "overflow" | "validate") = command {
+        let rounds: u64 = match args.get(2).map(|arg| arg.parse()) {
+            Some(Ok(rounds)) => rounds,
+            _ => panic!("Missing or invalid number of rounds"),
+        };
+        let divisor: u64 = match args.get(3).map(|arg| arg.parse()) {
+            None => 1,
+            Some(Ok(divisor)) if divisor > 0 => divisor,
+            Some(_) => panic!("Invalid divisor"),
+        };
+        let mut monkeys = parse_file(&fname);
+        let result = match command {
+            Some("rounds") => count_inspections(&monkeys, &rounds)
+                .map(|inspections| get_monkey_business(&inspections).to_string()),
+            Some("exact") => simulate_exact(&monkeys, &rounds, &divisor)
+                .map(|game| get_monkey_business(&game.inspections).to_string()),
+            Some("overflow") => {
+                find_overflow(&mut monkeys, &rounds, &Relief { divisor }).map(|overflow| {
+                    match overflow {
+                        Some(overflow) => format!(
+                        "monkey {} overflows in round {} inspecting an item with worry level {}",
+                        overflow.monkey, overflow.round, overflow.worry
+                    ),
+                        None => String::from("no overflow"),
+                    }
+                })
+            }
+            _ => validate_modulo(&monkeys, &rounds).map(|valid| match valid {
+                true => String::from("modulo and exact inspections match"),
+                false => String::from("modulo and exact inspections differ"),
+            }),
+        };
+        match result {
+            Ok(output) => println!("{}", output),
+            Err(why) => panic!("{}", why),
+        }
+        return;
+    }
+
     // part 1
     let result = solve_part1(&fname);
     println!("Solution to part 1: {}", result);